@@ -37,17 +37,17 @@ impl From<&ActivateJobsArgs> for ActivateJobsRequest {
 
 #[async_trait]
 impl ExecuteZeebeCommand for ActivateJobsArgs {
-    type Output = Vec<ActivateJobsResponse>;
+    type Output = ActivateJobsResponse;
 
     #[tracing::instrument(skip(client))]
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
         let args = &self;
         let request: ActivateJobsRequest = args.into();
         let mut stream = client.activate_jobs(request).await?.into_inner();
-        let mut result = Vec::with_capacity(args.max_jobs_to_activate);
+        let mut jobs = Vec::with_capacity(args.max_jobs_to_activate);
         while let Some(response) = stream.message().await? {
-            result.push(response);
+            jobs.extend(response.jobs);
         }
-        Ok(result)
+        Ok(ActivateJobsResponse { jobs })
     }
 }
@@ -0,0 +1,390 @@
+use std::{path::PathBuf, sync::Arc};
+
+use clap::{ArgAction, Args, CommandFactory, FromArgMatches};
+use color_eyre::eyre::{eyre, Report, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    sync::Semaphore,
+    task::JoinSet,
+};
+use zeebe_client::ZeebeClient;
+
+use crate::{
+    output::write_line,
+    shell::{check_runnable, Line},
+    Commands,
+};
+
+#[derive(Args, Debug)]
+pub(crate) struct BatchArgs {
+    /// NDJSON file with one command per line, or `-` to read stdin. Each line is an object like
+    /// `{"command": "publish-message", "args": {"name": "ping", "correlationKey": "42"}}`, where
+    /// `args` holds the options of the command by name, or its command line words as an array
+    file: PathBuf,
+    /// Number of commands run at the same time
+    #[arg(long, default_value_t = 16)]
+    concurrency: usize,
+}
+
+/// Number of commands that succeeded and failed, printed to stderr once all commands are done.
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct BatchSummary {
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    command: String,
+    #[serde(default)]
+    args: EntryArgs,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EntryArgs {
+    Words(Vec<Value>),
+    Options(Map<String, Value>),
+}
+
+impl Default for EntryArgs {
+    fn default() -> Self {
+        EntryArgs::Options(Map::new())
+    }
+}
+
+/// The result of a line of input, printed as a line of NDJSON to stdout.
+#[derive(Serialize, Debug)]
+struct BatchResult {
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl BatchArgs {
+    /// Runs the commands of the input with `client`, at most `concurrency` at a time. The results
+    /// are printed in the order the commands finish, each with the number of its input line.
+    pub(crate) async fn execute(self, client: ZeebeClient) -> Result<BatchSummary> {
+        let input: Box<dyn AsyncBufRead + Unpin + Send> = if self.file.as_os_str() == "-" {
+            Box::new(BufReader::new(tokio::io::stdin()))
+        } else {
+            let file = tokio::fs::File::open(&self.file)
+                .await
+                .wrap_err_with(|| format!("failed to read {}", self.file.display()))?;
+            Box::new(BufReader::new(file))
+        };
+
+        let slots = Arc::new(Semaphore::new(self.concurrency.max(1)));
+        let mut running = JoinSet::new();
+        let mut summary = BatchSummary::default();
+        let mut lines = input.lines();
+        let mut number = 0;
+        while let Some(line) = lines.next_line().await? {
+            number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let slot = slots.clone().acquire_owned().await?;
+            let mut client = client.clone();
+            running.spawn(async move {
+                let result = run(number, &line, &mut client).await;
+                drop(slot);
+                write_line(&serde_json::to_string(&result).expect("results serialize"))
+                    .map(|()| result.ok)
+            });
+            while let Some(ok) = running.try_join_next() {
+                summary.count(ok??);
+            }
+        }
+        while let Some(ok) = running.join_next().await {
+            summary.count(ok??);
+        }
+
+        eprintln!("{}", serde_json::to_string(&summary)?);
+        if summary.failed > 0 {
+            return Err(eyre!(
+                "{} of {} commands failed",
+                summary.failed,
+                summary.total
+            ));
+        }
+        Ok(summary)
+    }
+}
+
+impl BatchSummary {
+    fn count(&mut self, ok: bool) {
+        self.total += 1;
+        if ok {
+            self.succeeded += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+}
+
+async fn run(line: usize, input: &str, client: &mut ZeebeClient) -> BatchResult {
+    let entry = match serde_json::from_str::<Entry>(input) {
+        Ok(entry) => entry,
+        Err(err) => {
+            return BatchResult {
+                line,
+                command: None,
+                ok: false,
+                response: None,
+                error: Some(format!("invalid line: {}", err)),
+            }
+        }
+    };
+    let command = entry.command.clone();
+    let response = match parse(entry) {
+        Ok(parsed) => crate::execute(parsed, client, None).await,
+        Err(err) => Err(err),
+    };
+    match response {
+        Ok(response) => BatchResult {
+            line,
+            command: Some(command),
+            ok: true,
+            response: Some(response),
+            error: None,
+        },
+        Err(err) => BatchResult {
+            line,
+            command: Some(command),
+            ok: false,
+            response: None,
+            error: Some(format!("{:#}", err)),
+        },
+    }
+}
+
+/// Parses an entry like the command line, so that it takes the same options with the same
+/// validation.
+fn parse(entry: Entry) -> Result<Commands> {
+    let mut words = vec![entry.command.clone()];
+    match entry.args {
+        EntryArgs::Words(args) => words.extend(args.into_iter().flat_map(values)),
+        EntryArgs::Options(options) => words.extend(to_words(&entry.command, options)?),
+    }
+    let matches = Line::command()
+        .try_get_matches_from(words)
+        .map_err(clap_error)?;
+    let line = Line::from_arg_matches(&matches).map_err(clap_error)?;
+    check_runnable(&matches, &line.command, "a batch")?;
+    Ok(line.command)
+}
+
+/// The message of a clap error, without the usage that follows it after a blank line.
+fn clap_error(err: clap::Error) -> Report {
+    let message = err.to_string();
+    let message = message
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ");
+    eyre!("{}", message.trim_start_matches("error: "))
+}
+
+/// Turns options given by name into command line words. Names may be camelCase, kebab-case or
+/// snake_case. Positional arguments are given by name as well.
+fn to_words(command: &str, options: Map<String, Value>) -> Result<Vec<String>> {
+    let mut line = Line::command();
+    // assigns the indices of positional arguments
+    line.build();
+    let command = line
+        .find_subcommand(command)
+        .ok_or_else(|| eyre!("unknown command {}", command))?;
+
+    let mut words = Vec::new();
+    let mut positionals = Vec::new();
+    for (name, value) in options {
+        let id = snake_case(&name);
+        let arg = command
+            .get_arguments()
+            .find(|arg| {
+                arg.get_id() == id.as_str()
+                    || arg.get_long().map(|long| long.replace('-', "_")) == Some(id.clone())
+            })
+            .ok_or_else(|| eyre!("unknown argument {}", name))?;
+
+        if arg.is_positional() {
+            positionals.push((arg.get_index(), values(value)));
+            continue;
+        }
+        let long = arg
+            .get_long()
+            .ok_or_else(|| eyre!("unknown argument {}", name))?;
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            match value {
+                Value::Bool(true) => words.push(format!("--{}", long)),
+                Value::Bool(false) | Value::Null => {}
+                other => return Err(eyre!("{} takes true or false, got {}", name, other)),
+            }
+            continue;
+        }
+        // joined with `=`, as values such as negative numbers would be taken for options
+        words.extend(
+            values(value)
+                .into_iter()
+                .map(|value| format!("--{}={}", long, value)),
+        );
+    }
+
+    positionals.sort_by_key(|(index, _)| *index);
+    if !positionals.is_empty() {
+        words.push("--".to_owned());
+        words.extend(positionals.into_iter().flat_map(|(_, values)| values));
+    }
+    Ok(words)
+}
+
+/// The values of an argument. Arrays repeat the argument, and objects are passed as JSON.
+fn values(value: Value) -> Vec<String> {
+    match value {
+        Value::Null => vec![],
+        Value::String(value) => vec![value],
+        Value::Array(items) => items.into_iter().flat_map(values).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for c in name.chars() {
+        match c {
+            '-' => snake.push('_'),
+            c if c.is_ascii_uppercase() => {
+                snake.push('_');
+                snake.push(c.to_ascii_lowercase());
+            }
+            c => snake.push(c),
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn options(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(options) => options,
+            other => panic!("not an object: {}", other),
+        }
+    }
+
+    fn error(entry: Value) -> String {
+        let entry: Entry = serde_json::from_value(entry).unwrap();
+        match parse(entry) {
+            Ok(_) => panic!("parsed an invalid entry"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn converts_names_to_snake_case() {
+        assert_eq!(snake_case("correlationKey"), "correlation_key");
+        assert_eq!(snake_case("correlation-key"), "correlation_key");
+        assert_eq!(snake_case("correlation_key"), "correlation_key");
+        assert_eq!(snake_case("name"), "name");
+    }
+
+    #[test]
+    fn turns_options_into_words() {
+        let words = to_words(
+            "create-process-instance",
+            options(json!({
+                "bpmnProcessId": "order",
+                "start-before": ["a", "b"],
+                "with_results": true,
+                "variables": { "amount": 42 },
+            })),
+        )
+        .unwrap();
+        assert_eq!(
+            words,
+            [
+                "--bpmn-process-id=order",
+                "--start-before=a",
+                "--start-before=b",
+                "--variables={\"amount\":42}",
+                "--with-results",
+            ]
+        );
+    }
+
+    #[test]
+    fn passes_positionals_after_options() {
+        let words = to_words(
+            "activate-jobs",
+            options(json!({ "jobType": "payment", "jobTimeout": -1 })),
+        )
+        .unwrap();
+        assert_eq!(words, ["--job-timeout=-1", "--", "payment"]);
+    }
+
+    #[test]
+    fn rejects_flags_without_booleans() {
+        let err = to_words(
+            "create-process-instance",
+            options(json!({ "withResults": "yes" })),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "withResults takes true or false, got \"yes\""
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_arguments() {
+        let err = to_words("activate-jobs", options(json!({ "withResults": true }))).unwrap_err();
+        assert_eq!(err.to_string(), "unknown argument withResults");
+    }
+
+    #[test]
+    fn rejects_unknown_commands() {
+        let err = to_words("deploy-everything", Map::new()).unwrap_err();
+        assert_eq!(err.to_string(), "unknown command deploy-everything");
+    }
+
+    #[test]
+    fn rejects_reading_stdin() {
+        assert_eq!(
+            error(json!({
+                "command": "complete-job",
+                "args": { "jobKey": 1, "variables": "-" },
+            })),
+            "--variables can't read stdin in a batch"
+        );
+        assert_eq!(
+            error(json!({
+                "command": "complete-job",
+                "args": ["1", "--variables", "-"],
+            })),
+            "--variables can't read stdin in a batch"
+        );
+    }
+
+    #[test]
+    fn rejects_commands_outside_the_gateway() {
+        assert_eq!(
+            error(json!({ "command": "shell" })),
+            "shell is not available in a batch"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Debug, ExecuteZeebeCommand};
+use crate::{variables::VariablesArgs, Debug, ExecuteZeebeCommand};
 use async_trait::async_trait;
 use clap::Args;
 use color_eyre::eyre::Result;
@@ -11,16 +11,18 @@ use zeebe_client::{
 pub(crate) struct CompleteJobArgs {
     job_key: i64,
 
-    #[arg(long, required = false, default_value = "")]
-    variables: String,
+    #[command(flatten)]
+    variables: VariablesArgs,
 }
 
-impl From<&CompleteJobArgs> for CompleteJobRequest {
-    fn from(args: &CompleteJobArgs) -> Self {
-        CompleteJobRequest {
+impl TryFrom<&CompleteJobArgs> for CompleteJobRequest {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: &CompleteJobArgs) -> Result<Self> {
+        Ok(CompleteJobRequest {
             job_key: args.job_key,
-            variables: args.variables.clone(),
-        }
+            variables: args.variables.to_json()?,
+        })
     }
 }
 
@@ -31,7 +33,7 @@ impl ExecuteZeebeCommand for CompleteJobArgs {
     #[tracing::instrument(skip(client))]
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
         let args = &self;
-        let request: CompleteJobRequest = args.into();
+        let request: CompleteJobRequest = args.try_into()?;
         Ok(client.complete_job(request).await?.into_inner())
     }
 }
@@ -0,0 +1,20 @@
+use clap::{Args, Command};
+use clap_complete::Shell;
+use color_eyre::eyre::Result;
+
+use crate::output::write_line;
+
+#[derive(Args)]
+pub(crate) struct CompletionsArgs {
+    shell: Shell,
+}
+
+impl CompletionsArgs {
+    /// Prints the completion script of `command` for the shell.
+    pub(crate) fn execute(self, mut command: Command) -> Result<()> {
+        let name = command.get_name().to_owned();
+        let mut script = vec![];
+        clap_complete::generate(self.shell, &mut command, name, &mut script);
+        write_line(String::from_utf8_lossy(&script).trim_end())
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::output::OutputFormat;
+
+/// Name of the profile used when none is selected.
+const DEFAULT_PROFILE: &str = "default";
+
+/// `~/.config/zeebe/config.toml`, or below `$XDG_CONFIG_HOME` if it is set.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("zeebe").join("config.toml"))
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct Config {
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Connection settings and defaults for a cluster.
+#[derive(Args, Serialize, Deserialize, Clone, Debug, Default)]
+pub(crate) struct Profile {
+    /// Address of the gateway, as `<host>:<port>`
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) address: Option<String>,
+    /// Connect without TLS
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) insecure: bool,
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ca_certificate: Option<PathBuf>,
+    #[arg(long, requires = "client_key")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) client_certificate: Option<PathBuf>,
+    #[arg(long, requires = "client_certificate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) client_key: Option<PathBuf>,
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) client_id: Option<String>,
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) client_secret: Option<String>,
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) authorization_server: Option<String>,
+    /// Output format used unless `--output` is given
+    #[arg(long = "default-output", id = "default_output", value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) output: Option<OutputFormat>,
+}
+
+impl Config {
+    /// Reads the config file at `path`, which is empty if the file doesn't exist.
+    pub(crate) fn load(path: &Path) -> Result<Config> {
+        match std::fs::read_to_string(path) {
+            Ok(config) => toml::from_str(&config)
+                .wrap_err_with(|| format!("invalid config file {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(err).wrap_err_with(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    /// Writes the config file, which only the current user may read as it can hold secrets.
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let config = toml::to_string_pretty(self)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(config.as_bytes()))
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The profile `name`, or the default profile if there is one and no name is given.
+    pub(crate) fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| eyre!("Unknown profile {}", name)),
+            None => Ok(self
+                .profiles
+                .get(DEFAULT_PROFILE)
+                .cloned()
+                .unwrap_or_default()),
+        }
+    }
+}
+
+#[derive(Args)]
+pub(crate) struct ProfileArgs {
+    #[command(subcommand)]
+    command: ProfileCommand,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// Lists the profiles in the config file
+    List,
+    /// Adds a profile, replacing one of the same name
+    Add {
+        name: String,
+        #[command(flatten)]
+        profile: Profile,
+    },
+    /// Removes a profile
+    Remove { name: String },
+    /// Shows a profile, without its client secret
+    Show { name: String },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum ProfileOutput {
+    List(Vec<NamedProfile>),
+    Profile(NamedProfile),
+}
+
+/// A profile as printed by the profile commands. Its settings are camelCase like the rest of
+/// the output, while the config file keeps them snake_case like the options.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct NamedProfile {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    insecure: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ca_certificate: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_certificate: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_key: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<OutputFormat>,
+}
+
+impl NamedProfile {
+    fn new(name: String, profile: Profile) -> NamedProfile {
+        let Profile {
+            address,
+            insecure,
+            ca_certificate,
+            client_certificate,
+            client_key,
+            client_id,
+            client_secret,
+            authorization_server,
+            output,
+        } = profile;
+        NamedProfile {
+            name,
+            address,
+            insecure,
+            ca_certificate,
+            client_certificate,
+            client_key,
+            client_id,
+            client_secret: client_secret.map(|_| "********".to_owned()),
+            authorization_server,
+            output,
+        }
+    }
+}
+
+impl ProfileArgs {
+    pub(crate) fn execute(self, path: &Path) -> Result<ProfileOutput> {
+        let mut config = Config::load(path)?;
+        match self.command {
+            ProfileCommand::List => Ok(ProfileOutput::List(
+                config
+                    .profiles
+                    .into_iter()
+                    .map(|(name, profile)| NamedProfile::new(name, profile))
+                    .collect(),
+            )),
+            ProfileCommand::Add { name, profile } => {
+                config.profiles.insert(name.clone(), profile.clone());
+                config.save(path)?;
+                Ok(ProfileOutput::Profile(NamedProfile::new(name, profile)))
+            }
+            ProfileCommand::Remove { name } => {
+                let profile = config
+                    .profiles
+                    .remove(&name)
+                    .ok_or_else(|| eyre!("Unknown profile {}", name))?;
+                config.save(path)?;
+                Ok(ProfileOutput::Profile(NamedProfile::new(name, profile)))
+            }
+            ProfileCommand::Show { name } => {
+                let profile = config.profile(Some(&name))?;
+                Ok(ProfileOutput::Profile(NamedProfile::new(name, profile)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_loads_profiles() {
+        let path = std::env::temp_dir()
+            .join(format!("zeebe-config-{}", std::process::id()))
+            .join("config.toml");
+        let profile = Profile {
+            address: Some("zeebe.prod:443".to_owned()),
+            insecure: true,
+            client_id: Some("id".to_owned()),
+            client_secret: Some("secret".to_owned()),
+            output: Some(OutputFormat::Yaml),
+            ..Profile::default()
+        };
+        let mut config = Config::default();
+        config.profiles.insert("prod".to_owned(), profile);
+        config.save(&path).unwrap();
+
+        let loaded = Config::load(&path).unwrap();
+        let prod = loaded.profile(Some("prod")).unwrap();
+        assert_eq!(prod.address.as_deref(), Some("zeebe.prod:443"));
+        assert!(prod.insecure);
+        assert_eq!(prod.client_secret.as_deref(), Some("secret"));
+        assert_eq!(prod.output, Some(OutputFormat::Yaml));
+        assert!(loaded.profile(Some("dev")).is_err());
+        // there is no default profile
+        assert!(loaded.profile(None).unwrap().address.is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn loads_nothing_without_file() {
+        let config = Config::load(Path::new("/nonexistent/config.toml")).unwrap();
+        assert!(config.profiles.is_empty());
+    }
+}
@@ -1,39 +1,79 @@
-use crate::{Debug, ExecuteZeebeCommand};
+use crate::{variables::VariablesArgs, ExecuteZeebeCommand};
 use async_trait::async_trait;
 use clap::Args;
 use color_eyre::eyre::Result;
+use serde::Serialize;
 use zeebe_client::{
-    api::{CreateProcessInstanceRequest, CreateProcessInstanceWithResultRequest},
+    api::{
+        CreateProcessInstanceRequest, CreateProcessInstanceResponse,
+        CreateProcessInstanceWithResultRequest, CreateProcessInstanceWithResultResponse,
+    },
+    process_instance::CreateProcessInstance,
     ZeebeClient,
 };
 
 #[derive(Args, Clone, Debug)]
+#[command(group = clap::ArgGroup::new("process").required(true))]
 pub(crate) struct CreateProcessInstanceArgs {
-    process_instance_key: i64,
+    #[arg(short, long, group = "process")]
+    bpmn_process_id: Option<String>,
+    /// Version of the process, or `latest` (the default)
+    #[arg(long, conflicts_with = "process_definition_key", value_parser = parse_version)]
+    version: Option<i32>,
+    #[arg(long, group = "process")]
+    process_definition_key: Option<i64>,
 
     #[arg(long, required = false)]
     with_results: bool,
-    #[arg(long, required = false, default_value = "")]
-    variables: String,
-    #[arg(long, required = false, default_value_t = -1)]
-    version: i32,
+    #[command(flatten)]
+    variables: VariablesArgs,
+    /// Starts the instance before this element instead of at the start event, can be repeated
+    #[arg(long)]
+    start_before: Vec<String>,
+    /// Variables to return with the results, all of them if none are given
+    #[arg(long, requires = "with_results")]
+    fetch_variables: Vec<String>,
+    /// Timeout in ms to wait for the results, the gateway's default if 0
+    #[arg(long, requires = "with_results", default_value_t = 0)]
+    request_timeout: i64, // todo: should be duration
 }
 
-impl From<&CreateProcessInstanceArgs> for CreateProcessInstanceRequest {
-    fn from(args: &CreateProcessInstanceArgs) -> Self {
-        CreateProcessInstanceRequest {
-            process_definition_key: args.process_instance_key,
-            bpmn_process_id: String::new(),
-            version: args.version,
-            variables: args.variables.clone(),
-            start_instructions: vec![],
+fn parse_version(version: &str) -> Result<i32, String> {
+    match version {
+        "latest" => Ok(-1),
+        version => version
+            .parse::<i32>()
+            .ok()
+            .filter(|version| *version > 0)
+            .ok_or_else(|| format!("expected a positive number or `latest`, got {}", version)),
+    }
+}
+
+impl TryFrom<&CreateProcessInstanceArgs> for CreateProcessInstanceRequest {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: &CreateProcessInstanceArgs) -> Result<Self> {
+        let mut builder = match (&args.bpmn_process_id, args.process_definition_key) {
+            (Some(bpmn_process_id), _) => {
+                CreateProcessInstance::with_bpmn_process_id(bpmn_process_id)
+                    .version(args.version.unwrap_or(-1))
+            }
+            (None, Some(process_definition_key)) => {
+                CreateProcessInstance::with_process_definition_key(process_definition_key)
+            }
+            (None, None) => unreachable!("Should be a BPMN process id or a definition key"),
         }
+        .variables(args.variables.to_json()?);
+        for element_id in &args.start_before {
+            builder = builder.start_before(element_id);
+        }
+        Ok(builder.build())
     }
 }
 
 #[async_trait]
 impl ExecuteZeebeCommand for CreateProcessInstanceArgs {
-    type Output = Box<dyn Debug>;
+    type Output = CreateProcessInstanceOutput;
 
     #[tracing::instrument(skip(client))]
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
@@ -41,23 +81,99 @@ impl ExecuteZeebeCommand for CreateProcessInstanceArgs {
     }
 }
 
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub(crate) enum CreateProcessInstanceOutput {
+    Created(CreateProcessInstanceResponse),
+    Completed(CreateProcessInstanceWithResultResponse),
+}
+
 async fn handle_create_instance_command(
     client: &mut ZeebeClient,
     args: &CreateProcessInstanceArgs,
-) -> Result<Box<dyn Debug>> {
-    let request: CreateProcessInstanceRequest = args.into();
+) -> Result<CreateProcessInstanceOutput> {
+    let request: CreateProcessInstanceRequest = args.try_into()?;
     match args.with_results {
-        true => Ok(Box::new(
+        true => Ok(CreateProcessInstanceOutput::Completed(
             client
                 .create_process_instance_with_result(CreateProcessInstanceWithResultRequest {
                     request: Some(request),
-                    ..Default::default()
+                    request_timeout: args.request_timeout,
+                    fetch_variables: args.fetch_variables.clone(),
                 })
                 .await?
                 .into_inner(),
         )),
-        false => Ok(Box::new(
+        false => Ok(CreateProcessInstanceOutput::Created(
             client.create_process_instance(request).await?.into_inner(),
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::*;
+    use crate::{Cli, Commands};
+
+    fn parse(args: &[&str]) -> Result<CreateProcessInstanceArgs, clap::Error> {
+        let args = ["zeebe-cli", "create-process-instance"].iter().chain(args);
+        match Cli::try_parse_from(args)?.command {
+            Commands::CreateProcessInstance(args) => Ok(args),
+            _ => unreachable!("parsed another command"),
+        }
+    }
+
+    #[test]
+    fn checks_the_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!(parse_version("latest"), Ok(-1));
+        assert_eq!(parse_version("3"), Ok(3));
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        assert!(parse_version("0").is_err());
+        assert!(parse_version("-1").is_err());
+        assert!(parse_version("v3").is_err());
+    }
+
+    #[test]
+    fn requires_a_process() {
+        let err = parse(&[]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+        let args = parse(&["--bpmn-process-id", "order", "--version", "latest"]).unwrap();
+        assert_eq!(args.bpmn_process_id.as_deref(), Some("order"));
+        assert_eq!(args.version, Some(-1));
+
+        let args = parse(&["--process-definition-key", "2251799813685249"]).unwrap();
+        assert_eq!(args.process_definition_key, Some(2251799813685249));
+    }
+
+    #[test]
+    fn rejects_more_than_one_process() {
+        let err = parse(&[
+            "--bpmn-process-id",
+            "order",
+            "--process-definition-key",
+            "2251799813685249",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let err = parse(&[
+            "--process-definition-key",
+            "2251799813685249",
+            "--version",
+            "2",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use color_eyre::eyre::Result;
 
-use crate::ExecuteZeebeCommand;
+use crate::{variables::VariablesArgs, ExecuteZeebeCommand};
 use clap::Args;
 
 use zeebe_client::{
@@ -24,19 +24,21 @@ pub(crate) struct FailJobArgs {
     // the back off timeout for the next retry
     #[arg(long, required = false, default_value_t = 0)]
     retry_back_off: i64,
-    #[arg(long, required = false, default_value = "")]
-    variables: String,
+    #[command(flatten)]
+    variables: VariablesArgs,
 }
 
-impl From<&FailJobArgs> for FailJobRequest {
-    fn from(args: &FailJobArgs) -> Self {
-        FailJobRequest {
+impl TryFrom<&FailJobArgs> for FailJobRequest {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: &FailJobArgs) -> Result<Self> {
+        Ok(FailJobRequest {
             job_key: args.job_key,
             retries: args.retries,
             error_message: args.error_message.to_owned(),
             retry_back_off: args.retry_back_off,
-            variables: args.variables.clone(),
-        }
+            variables: args.variables.to_json()?,
+        })
     }
 }
 
@@ -46,7 +48,7 @@ impl ExecuteZeebeCommand for FailJobArgs {
 
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
         Ok(client
-            .fail_job(FailJobRequest::from(&self))
+            .fail_job(FailJobRequest::try_from(&self)?)
             .await?
             .into_inner())
     }
@@ -1,22 +1,34 @@
 mod activate_jobs;
+mod batch;
 mod cancel_process_instance;
 mod complete_job;
+mod completions;
+mod config;
 mod create_process_instance;
 mod deploy_resource;
 mod fail_job;
+mod man;
+mod modify_process_instance;
+mod output;
 mod publish_message;
 mod resolve_incident;
 mod set_variables;
+mod shell;
 mod status;
 mod throw_error;
 mod update_retries;
+mod variables;
+mod work;
 
-use std::fmt::Debug;
+use std::{fmt::Debug, path::PathBuf};
 
 use async_trait::async_trait;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 
 use color_eyre::eyre::Result;
+use config::{Config, Profile};
+use output::OutputFormat;
+use serde::Serialize;
 use zeebe_client::ZeebeClient;
 
 #[derive(Parser)]
@@ -26,6 +38,15 @@ struct Cli {
     connection: Connection,
     #[clap(flatten)]
     auth: Authentication,
+    /// Output format [default: json]
+    #[arg(long, value_enum, global = true)]
+    output: Option<OutputFormat>,
+    /// Profile of the config file to use, `default` if there is one and no other is given
+    #[arg(long, global = true, env = "ZEEBE_PROFILE")]
+    profile: Option<String>,
+    /// Config file with profiles [default: ~/.config/zeebe/config.toml]
+    #[arg(long, global = true, env = "ZEEBE_CONFIG_FILE")]
+    config: Option<PathBuf>,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -36,39 +57,50 @@ struct Authentication {
     client_id: Option<String>,
     #[arg(long, env = "ZEEBE_CLIENT_SECRET")]
     client_secret: Option<String>,
-    #[arg(
-        long,
-        env = "ZEEBE_AUTHORIZATION_SERVER_URL",
-        default_value = "https://login.cloud.camunda.io/oauth/token/"
-    )]
-    authorization_server: String,
+    /// [default: https://login.cloud.camunda.io/oauth/token/]
+    #[arg(long, env = "ZEEBE_AUTHORIZATION_SERVER_URL")]
+    authorization_server: Option<String>,
 }
 
 #[derive(Parser)]
 #[command(group = clap::ArgGroup::new("connection"))]
 struct Connection {
-    #[arg(long)]
-    insecure: bool,
+    /// Connect without TLS, or with TLS by `--insecure=false` if the profile is insecure
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        env = "ZEEBE_INSECURE"
+    )]
+    insecure: Option<bool>,
 
     #[arg(long, group = "connection", env = "ZEEBE_ADDRESS")]
     address: Option<String>,
 
+    /// [default: localhost]
     #[arg(
         long,
         group = "connection",
         conflicts_with = "address",
-        default_value = "localhost",
         env = "ZEEBE_HOST"
     )]
-    host: String,
+    host: Option<String>,
+    /// [default: 26500]
     #[arg(
         long,
         group = "connection",
         conflicts_with = "address",
         value_parser = clap::value_parser!(u16).range(1..),
-        default_value_t = 26500,
         env = "ZEEBE_PORT")]
-    port: u16,
+    port: Option<u16>,
+
+    #[arg(long, env = "ZEEBE_CA_CERTIFICATE_PATH")]
+    ca_certificate: Option<PathBuf>,
+    #[arg(long, requires = "client_key", env = "ZEEBE_CLIENT_CERTIFICATE_PATH")]
+    client_certificate: Option<PathBuf>,
+    #[arg(long, requires = "client_certificate", env = "ZEEBE_CLIENT_KEY_PATH")]
+    client_key: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -82,6 +114,7 @@ enum Commands {
     // process instance
     CreateProcessInstance(create_process_instance::CreateProcessInstanceArgs),
     CancelProcessInstance(cancel_process_instance::CancelProcessInstanceArgs),
+    ModifyProcessInstance(modify_process_instance::ModifyProcessInstanceArgs),
 
     // message
     PublishMessage(publish_message::PublishMessageArgs),
@@ -98,29 +131,75 @@ enum Commands {
     FailJob(fail_job::FailJobArgs),
     UpdateRetries(update_retries::UpdateRetriesArgs),
     ThrowError(throw_error::ThrowErrorArgs),
+    /// Keeps activating jobs and runs a command for each
+    Work(work::WorkArgs),
+
+    // configuration
+    /// Manages the connection profiles of the config file
+    Profile(config::ProfileArgs),
+
+    // interactive
+    /// Runs commands from a prompt, over one connection
+    Shell(shell::ShellArgs),
+    /// Runs the commands of an NDJSON file, over one connection
+    Batch(batch::BatchArgs),
+
+    // documentation
+    /// Prints a completion script for a shell
+    Completions(completions::CompletionsArgs),
+    /// Writes man pages for all commands
+    Man(man::ManArgs),
 }
 
-impl From<Connection> for zeebe_client::Connection {
-    fn from(conn: Connection) -> Self {
-        match conn.address {
-            Some(addr) => zeebe_client::Connection {
-                insecure: conn.insecure,
-                addr,
-            },
-            None => zeebe_client::Connection {
-                insecure: conn.insecure,
-                addr: format!("{}:{}", conn.host, conn.port),
+impl Connection {
+    /// Settings given as flags or environment variables take precedence over the profile. A host
+    /// or port alone replaces that part of the profile's address.
+    fn with_profile(self, profile: &Profile) -> zeebe_client::Connection {
+        let (profile_host, profile_port) = match profile.address.as_deref() {
+            Some(address) => match address.rsplit_once(':') {
+                Some((host, port)) => (Some(host), Some(port)),
+                None => (Some(address), None),
             },
+            None => (None, None),
+        };
+        let addr = match self.address {
+            Some(address) => address,
+            None => format!(
+                "{}:{}",
+                self.host.as_deref().or(profile_host).unwrap_or("localhost"),
+                self.port
+                    .map(|port| port.to_string())
+                    .as_deref()
+                    .or(profile_port)
+                    .unwrap_or("26500")
+            ),
+        };
+        let (client_certificate, client_key) = match self.client_certificate {
+            Some(certificate) => (Some(certificate), self.client_key),
+            None => (
+                profile.client_certificate.clone(),
+                profile.client_key.clone(),
+            ),
+        };
+        zeebe_client::Connection {
+            insecure: self.insecure.unwrap_or(profile.insecure),
+            addr,
+            ca_certificate: self.ca_certificate.or(profile.ca_certificate.clone()),
+            client_certificate,
+            client_key,
         }
     }
 }
 
 impl Authentication {
     fn for_connection(
-        &self,
+        self,
+        profile: &Profile,
         conn: &zeebe_client::Connection,
     ) -> Result<zeebe_client::Authentication> {
-        match (&self.client_id, &self.client_secret) {
+        let client_id = self.client_id.or(profile.client_id.clone());
+        let client_secret = self.client_secret.or(profile.client_secret.clone());
+        match (client_id, client_secret) {
             (None, None) => Ok(zeebe_client::Authentication::Unauthenticated),
             (Some(client_id), Some(client_secret)) => {
                 let audience = conn
@@ -131,9 +210,14 @@ impl Authentication {
                     .to_owned();
                 Ok(zeebe_client::Authentication::Oauth2(
                     zeebe_client::auth::OAuth2Config {
-                        client_id: client_id.clone(),
-                        client_secret: client_secret.clone(),
-                        auth_server: self.authorization_server.clone(),
+                        client_id,
+                        client_secret,
+                        auth_server: self
+                            .authorization_server
+                            .or(profile.authorization_server.clone())
+                            .unwrap_or_else(|| {
+                                "https://login.cloud.camunda.io/oauth/token/".to_owned()
+                            }),
                         audience,
                     },
                 ))
@@ -147,7 +231,7 @@ impl Authentication {
 
 #[async_trait]
 trait ExecuteZeebeCommand {
-    type Output: Debug;
+    type Output: Debug + Serialize;
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output>;
 }
 
@@ -163,11 +247,54 @@ fn install_tracing() {
 
     tracing_subscriber::registry()
         .with(filter_layer)
-        .with(HierarchicalLayer::new(2))
+        // keep stdout for the response, so it can be piped into other tools
+        .with(HierarchicalLayer::new(2).with_writer(std::io::stderr))
         .with(ErrorLayer::default())
         .init();
 }
 
+/// Runs a command that needs a connection to the gateway and returns its response as JSON. The
+/// response is also printed if an output format is given.
+async fn execute(
+    command: Commands,
+    client: &mut ZeebeClient,
+    output: Option<OutputFormat>,
+) -> Result<serde_json::Value> {
+    match command {
+        Commands::ActivateJobs(args) => respond(args.execute(client).await?, output),
+        Commands::CancelProcessInstance(args) => respond(args.execute(client).await?, output),
+        Commands::CompleteJob(args) => respond(args.execute(client).await?, output),
+        Commands::CreateProcessInstance(args) => respond(args.execute(client).await?, output),
+        Commands::DeployResource(args) => respond(args.execute(client).await?, output),
+        Commands::FailJob(args) => respond(args.execute(client).await?, output),
+        Commands::ModifyProcessInstance(args) => respond(args.execute(client).await?, output),
+        Commands::PublishMessage(args) => respond(args.execute(client).await?, output),
+        Commands::ResolveIncident(args) => respond(args.execute(client).await?, output),
+        Commands::SetVariables(args) => respond(args.execute(client).await?, output),
+        Commands::Status(args) => respond(args.execute(client).await?, output),
+        Commands::ThrowError(args) => respond(args.execute(client).await?, output),
+        Commands::UpdateRetries(args) => respond(args.execute(client).await?, output),
+        Commands::Work(args) => respond(args.execute(client).await?, output),
+        Commands::Profile(_)
+        | Commands::Completions(_)
+        | Commands::Man(_)
+        | Commands::Shell(_)
+        | Commands::Batch(_) => Err(color_eyre::eyre::eyre!(
+            "Command is only available outside the shell"
+        )),
+    }
+}
+
+fn respond<T: Serialize + Debug>(
+    response: T,
+    output: Option<OutputFormat>,
+) -> Result<serde_json::Value> {
+    match output {
+        Some(output) => output.print(&response),
+        None => output::to_json(&response),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     install_tracing();
@@ -175,25 +302,237 @@ async fn main() -> Result<()> {
     color_eyre::install()?;
 
     let cli: Cli = Cli::parse();
-    let conn: zeebe_client::Connection = cli.connection.into();
-    let mut client: ZeebeClient =
-        zeebe_client::connect(conn.clone(), cli.auth.for_connection(&conn)?).await?;
-    let response: Box<dyn Debug> = match cli.command {
-        Commands::ActivateJobs(args) => Box::new(args.execute(&mut client).await?),
-        Commands::CancelProcessInstance(args) => Box::new(args.execute(&mut client).await?),
-        Commands::CompleteJob(args) => Box::new(args.execute(&mut client).await?),
-        Commands::CreateProcessInstance(args) => args.execute(&mut client).await?, // Already boxed, because it could be one of two results
-        Commands::DeployResource(args) => Box::new(args.execute(&mut client).await?),
-        Commands::FailJob(args) => Box::new(args.execute(&mut client).await?),
-        Commands::PublishMessage(args) => Box::new(args.execute(&mut client).await?),
-        Commands::ResolveIncident(args) => Box::new(args.execute(&mut client).await?),
-        Commands::SetVariables(args) => Box::new(args.execute(&mut client).await?),
-        Commands::Status(args) => Box::new(args.execute(&mut client).await?),
-        Commands::ThrowError(args) => Box::new(args.execute(&mut client).await?),
-        Commands::UpdateRetries(args) => Box::new(args.execute(&mut client).await?),
+    // only needed for profiles and the shell history, the other commands work without one
+    let config_path = cli.config.or_else(config::default_path);
+    let required_config_path = || {
+        config_path
+            .as_deref()
+            .ok_or_else(|| color_eyre::eyre::eyre!("No config file, needs --config or $HOME"))
     };
-
-    println!("{:#?}", response);
+    let output = cli.output;
+    match cli.command {
+        Commands::Profile(args) => {
+            output
+                .unwrap_or(OutputFormat::Json)
+                .print(&args.execute(required_config_path()?)?)?;
+            return Ok(());
+        }
+        Commands::Completions(args) => {
+            args.execute(Cli::command())?;
+            return Ok(());
+        }
+        Commands::Man(args) => {
+            output
+                .unwrap_or(OutputFormat::Json)
+                .print(&args.execute(Cli::command())?)?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    let profile = match (&config_path, cli.profile.as_deref()) {
+        (None, None) => Profile::default(),
+        (_, name) => Config::load(required_config_path()?)?.profile(name)?,
+    };
+    let output = output.or(profile.output).unwrap_or(OutputFormat::Json);
+    let conn = cli.connection.with_profile(&profile);
+    let mut client: ZeebeClient =
+        zeebe_client::connect(conn.clone(), cli.auth.for_connection(&profile, &conn)?).await?;
+    match cli.command {
+        Commands::Shell(args) => {
+            args.execute(client, output, required_config_path()?)
+                .await?
+        }
+        Commands::Batch(args) => {
+            args.execute(client).await?;
+        }
+        command => {
+            execute(command, &mut client, Some(output)).await?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection() -> Connection {
+        Connection {
+            insecure: None,
+            address: None,
+            host: None,
+            port: None,
+            ca_certificate: None,
+            client_certificate: None,
+            client_key: None,
+        }
+    }
+
+    fn authentication() -> Authentication {
+        Authentication {
+            client_id: None,
+            client_secret: None,
+            authorization_server: None,
+        }
+    }
+
+    fn profile() -> Profile {
+        Profile {
+            address: Some("zeebe.prod:443".to_owned()),
+            ..Profile::default()
+        }
+    }
+
+    #[test]
+    fn defaults_to_localhost() {
+        let conn = connection().with_profile(&Profile::default());
+        assert_eq!(conn.addr, "localhost:26500");
+        assert!(!conn.insecure);
+    }
+
+    #[test]
+    fn takes_the_address_of_the_profile() {
+        assert_eq!(connection().with_profile(&profile()).addr, "zeebe.prod:443");
+        let conn = Connection {
+            address: Some("zeebe.dev:26500".to_owned()),
+            ..connection()
+        };
+        assert_eq!(conn.with_profile(&profile()).addr, "zeebe.dev:26500");
+    }
+
+    #[test]
+    fn replaces_part_of_the_profile_address() {
+        let conn = Connection {
+            port: Some(26500),
+            ..connection()
+        };
+        assert_eq!(conn.with_profile(&profile()).addr, "zeebe.prod:26500");
+        let conn = Connection {
+            host: Some("zeebe.dev".to_owned()),
+            ..connection()
+        };
+        assert_eq!(conn.with_profile(&profile()).addr, "zeebe.dev:443");
+        let profile = Profile {
+            address: Some("zeebe.prod".to_owned()),
+            ..Profile::default()
+        };
+        let conn = Connection {
+            port: Some(443),
+            ..connection()
+        };
+        assert_eq!(conn.with_profile(&profile).addr, "zeebe.prod:443");
+    }
+
+    #[test]
+    fn overrides_an_insecure_profile() {
+        let profile = Profile {
+            insecure: true,
+            ..profile()
+        };
+        assert!(connection().with_profile(&profile).insecure);
+        let conn = Connection {
+            insecure: Some(false),
+            ..connection()
+        };
+        assert!(!conn.with_profile(&profile).insecure);
+    }
+
+    #[test]
+    fn parses_insecure_with_an_optional_value() {
+        let insecure = |args: &[&str]| {
+            Cli::try_parse_from(["zeebe-cli"].iter().chain(args).chain(&["status"]))
+                .unwrap()
+                .connection
+                .insecure
+        };
+        assert_eq!(insecure(&["--insecure"]), Some(true));
+        assert_eq!(insecure(&["--insecure=false"]), Some(false));
+    }
+
+    #[test]
+    fn takes_the_client_certificate_and_key_together() {
+        let profile = Profile {
+            client_certificate: Some("profile.crt".into()),
+            client_key: Some("profile.key".into()),
+            ..profile()
+        };
+        let conn = connection().with_profile(&profile);
+        assert_eq!(conn.client_certificate, Some("profile.crt".into()));
+        assert_eq!(conn.client_key, Some("profile.key".into()));
+
+        let conn = Connection {
+            client_certificate: Some("flag.crt".into()),
+            client_key: Some("flag.key".into()),
+            ..connection()
+        }
+        .with_profile(&profile);
+        assert_eq!(conn.client_certificate, Some("flag.crt".into()));
+        assert_eq!(conn.client_key, Some("flag.key".into()));
+    }
+
+    fn oauth(auth: Authentication, profile: &Profile) -> zeebe_client::auth::OAuth2Config {
+        let conn = connection().with_profile(profile);
+        match auth.for_connection(profile, &conn).unwrap() {
+            zeebe_client::Authentication::Oauth2(config) => config,
+            _ => panic!("unauthenticated"),
+        }
+    }
+
+    #[test]
+    fn is_unauthenticated_without_client_id() {
+        let conn = connection().with_profile(&profile());
+        assert!(matches!(
+            authentication().for_connection(&profile(), &conn).unwrap(),
+            zeebe_client::Authentication::Unauthenticated
+        ));
+    }
+
+    #[test]
+    fn takes_the_client_secret_of_the_profile() {
+        let profile = Profile {
+            client_secret: Some("secret".to_owned()),
+            authorization_server: Some("https://auth".to_owned()),
+            ..profile()
+        };
+        let auth = Authentication {
+            client_id: Some("flag".to_owned()),
+            ..authentication()
+        };
+        let config = oauth(auth, &profile);
+        assert_eq!(config.client_id, "flag");
+        assert_eq!(config.client_secret, "secret");
+        assert_eq!(config.auth_server, "https://auth");
+        assert_eq!(config.audience, "zeebe.prod");
+    }
+
+    #[test]
+    fn takes_the_client_id_and_secret_of_the_profile() {
+        let profile = Profile {
+            client_id: Some("profile".to_owned()),
+            client_secret: Some("secret".to_owned()),
+            ..profile()
+        };
+        let auth = Authentication {
+            client_secret: Some("other".to_owned()),
+            ..authentication()
+        };
+        let config = oauth(auth, &profile);
+        assert_eq!(config.client_id, "profile");
+        assert_eq!(config.client_secret, "other");
+        assert_eq!(
+            config.auth_server,
+            "https://login.cloud.camunda.io/oauth/token/"
+        );
+    }
+
+    #[test]
+    fn rejects_a_client_id_without_secret() {
+        let auth = Authentication {
+            client_id: Some("flag".to_owned()),
+            ..authentication()
+        };
+        let conn = connection().with_profile(&profile());
+        assert!(auth.for_connection(&profile(), &conn).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Command};
+use clap_mangen::Man;
+use color_eyre::eyre::{Result, WrapErr};
+
+#[derive(Args)]
+pub(crate) struct ManArgs {
+    /// Directory to write the pages to
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+}
+
+impl ManArgs {
+    /// Writes a man page for `command` and one for each of its subcommands, named like
+    /// `zeebe-cli-create-process-instance.1`, and returns their paths.
+    pub(crate) fn execute(self, mut command: Command) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(&self.dir)?;
+        // sets the names of subcommands to e.g. `zeebe-cli-status`
+        command.build();
+        let mut pages = vec![];
+        write_pages(&command, &self.dir, &mut pages)?;
+        Ok(pages)
+    }
+}
+
+fn write_pages(command: &Command, dir: &Path, pages: &mut Vec<PathBuf>) -> Result<()> {
+    let man = Man::new(command.clone());
+    let path = dir.join(man.get_filename());
+    let mut file = File::create(&path)?;
+    man.render(&mut file)
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    pages.push(path);
+    for subcommand in command.get_subcommands() {
+        write_pages(subcommand, dir, pages)?;
+    }
+    Ok(())
+}
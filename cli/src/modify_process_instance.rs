@@ -0,0 +1,294 @@
+use std::path::PathBuf;
+
+use crate::{variables, ExecuteZeebeCommand};
+use async_trait::async_trait;
+use clap::Args;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use zeebe_client::{
+    api::{ModifyProcessInstanceRequest, ModifyProcessInstanceResponse},
+    process_instance::{Activation, ModifyProcessInstance},
+    ZeebeClient,
+};
+
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("instruction")
+    .required(true)
+    .multiple(true)
+    .args(["activate", "terminate", "instructions"]))]
+pub(crate) struct ModifyProcessInstanceArgs {
+    process_instance_key: i64,
+
+    /// Element to activate, as `<elementId>` or `<elementId>@<ancestorElementInstanceKey>`
+    #[arg(long, value_parser = parse_activate)]
+    activate: Vec<ActivateArg>,
+    /// Key of an element instance to terminate
+    #[arg(long)]
+    terminate: Vec<i64>,
+    /// Variables to create with the activated elements, as `<variables>` for the global scope or
+    /// `<scopeId>=<variables>`, where `<variables>` is a JSON object, `@<path>` to read a JSON or
+    /// YAML file, or `-` to read stdin. They are created with the activation of the element
+    /// `scopeId` if there is one, and with the first activation otherwise
+    #[arg(long, value_parser = parse_variables)]
+    variables: Vec<VariablesArg>,
+    /// JSON or YAML file with further instructions
+    #[arg(long)]
+    instructions: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+struct ActivateArg {
+    element_id: String,
+    ancestor_element_instance_key: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+struct VariablesArg {
+    scope_id: String,
+    variables: String,
+}
+
+fn parse_activate(activate: &str) -> Result<ActivateArg, String> {
+    match activate.split_once('@') {
+        Some((element_id, ancestor)) => Ok(ActivateArg {
+            element_id: element_id.to_owned(),
+            ancestor_element_instance_key: Some(
+                ancestor
+                    .parse()
+                    .map_err(|_| format!("invalid ancestor element instance key {}", ancestor))?,
+            ),
+        }),
+        None => Ok(ActivateArg {
+            element_id: activate.to_owned(),
+            ancestor_element_instance_key: None,
+        }),
+    }
+}
+
+fn parse_variables(variables: &str) -> Result<VariablesArg, String> {
+    let global = variables.trim_start().starts_with(['{', '@']) || variables == "-";
+    let (scope_id, variables) = match global {
+        true => ("", variables),
+        false => variables
+            .split_once('=')
+            .ok_or_else(|| "expected <variables> or <scopeId>=<variables>".to_owned())?,
+    };
+    // files and stdin are read when the command runs
+    if !variables.starts_with('@') && variables != "-" {
+        variables::parse(variables).map_err(|err| err.to_string())?;
+    }
+    Ok(VariablesArg {
+        scope_id: scope_id.to_owned(),
+        variables: variables.to_owned(),
+    })
+}
+
+/// Instructions read from a file, in the shape of the gateway request.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Instructions {
+    #[serde(default)]
+    activate_instructions: Vec<ActivateInstruction>,
+    #[serde(default)]
+    terminate_instructions: Vec<TerminateInstruction>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ActivateInstruction {
+    element_id: String,
+    ancestor_element_instance_key: Option<i64>,
+    #[serde(default)]
+    variable_instructions: Vec<VariableInstruction>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct VariableInstruction {
+    variables: Map<String, Value>,
+    #[serde(default)]
+    scope_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TerminateInstruction {
+    element_instance_key: i64,
+}
+
+impl TryFrom<ModifyProcessInstanceArgs> for ModifyProcessInstanceRequest {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: ModifyProcessInstanceArgs) -> Result<ModifyProcessInstanceRequest> {
+        let instructions: Instructions = match &args.instructions {
+            // YAML is a superset of JSON, so this reads both
+            Some(path) => serde_yaml::from_str(
+                &std::fs::read_to_string(path)
+                    .wrap_err_with(|| format!("failed to read {}", path.display()))?,
+            )
+            .wrap_err_with(|| format!("invalid instructions in {}", path.display()))?,
+            None => Instructions::default(),
+        };
+
+        let mut activations = vec![];
+        for instruction in instructions.activate_instructions {
+            let mut activation = activation(
+                &instruction.element_id,
+                instruction.ancestor_element_instance_key,
+            );
+            for variables in instruction.variable_instructions {
+                activation = activation.scoped_variables(
+                    variables.scope_id,
+                    Value::Object(variables.variables).to_string(),
+                );
+            }
+            activations.push((instruction.element_id, activation));
+        }
+        for activate in args.activate {
+            let activation =
+                activation(&activate.element_id, activate.ancestor_element_instance_key);
+            activations.push((activate.element_id, activation));
+        }
+        for variables in args.variables {
+            let (_, activation) = match activations
+                .iter_mut()
+                .position(|(element_id, _)| *element_id == variables.scope_id)
+            {
+                Some(index) => &mut activations[index],
+                None => activations
+                    .first_mut()
+                    .ok_or_else(|| eyre!("--variables needs an element to --activate"))?,
+            };
+            let json = Value::Object(variables::parse(&variables.variables)?).to_string();
+            *activation = activation
+                .clone()
+                .scoped_variables(variables.scope_id, json);
+        }
+
+        let mut builder = ModifyProcessInstance::new(args.process_instance_key);
+        for (_, activation) in activations {
+            builder = builder.activate(activation);
+        }
+        let terminations = instructions
+            .terminate_instructions
+            .into_iter()
+            .map(|instruction| instruction.element_instance_key)
+            .chain(args.terminate);
+        for element_instance_key in terminations {
+            builder = builder.terminate(element_instance_key);
+        }
+        Ok(builder.build())
+    }
+}
+
+fn activation(element_id: &str, ancestor_element_instance_key: Option<i64>) -> Activation {
+    match ancestor_element_instance_key {
+        Some(key) => Activation::element(element_id).in_ancestor(key),
+        None => Activation::element(element_id),
+    }
+}
+
+#[async_trait]
+impl ExecuteZeebeCommand for ModifyProcessInstanceArgs {
+    type Output = ModifyProcessInstanceResponse;
+
+    #[tracing::instrument(skip(client))]
+    async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
+        Ok(client
+            .modify_process_instance(ModifyProcessInstanceRequest::try_from(self)?)
+            .await?
+            .into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_activations() {
+        let activate = parse_activate("review").unwrap();
+        assert_eq!(activate.element_id, "review");
+        assert_eq!(activate.ancestor_element_instance_key, None);
+
+        let activate = parse_activate("review@2251799813685310").unwrap();
+        assert_eq!(activate.element_id, "review");
+        assert_eq!(
+            activate.ancestor_element_instance_key,
+            Some(2251799813685310)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ancestors() {
+        assert!(parse_activate("review@").is_err());
+        assert!(parse_activate("review@parent").is_err());
+    }
+
+    #[test]
+    fn parses_variables() {
+        let variables = parse_variables(r#"{"approved": false}"#).unwrap();
+        assert_eq!(variables.scope_id, "");
+        assert_eq!(variables.variables, r#"{"approved": false}"#);
+
+        let variables = parse_variables(r#"subprocess={"approved": false}"#).unwrap();
+        assert_eq!(variables.scope_id, "subprocess");
+        assert_eq!(variables.variables, r#"{"approved": false}"#);
+
+        let variables = parse_variables("@variables.yaml").unwrap();
+        assert_eq!(variables.scope_id, "");
+        assert_eq!(variables.variables, "@variables.yaml");
+
+        let variables = parse_variables("subprocess=-").unwrap();
+        assert_eq!(variables.scope_id, "subprocess");
+        assert_eq!(variables.variables, "-");
+    }
+
+    fn args(variables: &str) -> ModifyProcessInstanceArgs {
+        ModifyProcessInstanceArgs {
+            process_instance_key: 2251799813685249,
+            activate: vec![parse_activate("review").unwrap()],
+            terminate: vec![],
+            variables: vec![parse_variables(variables).unwrap()],
+            instructions: None,
+        }
+    }
+
+    #[test]
+    fn reads_variables_from_files() {
+        let path = std::env::temp_dir().join(format!("modify-{}.yaml", std::process::id()));
+        std::fs::write(&path, "approved: false\n").unwrap();
+        let request =
+            ModifyProcessInstanceRequest::try_from(args(&format!("review=@{}", path.display())));
+        std::fs::remove_file(&path).unwrap();
+
+        let variables = &request.unwrap().activate_instructions[0].variable_instructions[0];
+        assert_eq!(variables.scope_id, "review");
+        assert_eq!(variables.variables, r#"{"approved":false}"#);
+    }
+
+    #[test]
+    fn names_files_it_cannot_read() {
+        let err = ModifyProcessInstanceRequest::try_from(args("@/nonexistent/variables.json"))
+            .unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/variables.json"));
+
+        let err = ModifyProcessInstanceRequest::try_from(ModifyProcessInstanceArgs {
+            instructions: Some(PathBuf::from("/nonexistent/instructions.yaml")),
+            ..args("{}")
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to read /nonexistent/instructions.yaml"
+        );
+    }
+
+    #[test]
+    fn rejects_variables_that_are_no_object() {
+        assert!(parse_variables("approved").is_err());
+        assert!(parse_variables("subprocess=[1, 2]").is_err());
+        assert!(parse_variables(r#"{"approved": }"#).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+};
+
+use clap::ValueEnum;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields that hold a JSON document serialized into a string.
+pub(crate) const EMBEDDED_JSON_FIELDS: &[&str] = &["variables", "customHeaders"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum OutputFormat {
+    Json,
+    Yaml,
+    Table,
+    /// Rust debug representation of the response
+    Debug,
+}
+
+impl OutputFormat {
+    /// Prints the response and returns it as JSON, with embedded JSON documents expanded.
+    pub(crate) fn print<T: Serialize + Debug>(self, response: &T) -> Result<Value> {
+        let value = to_json(response)?;
+        let rendered = match self {
+            OutputFormat::Json => serde_json::to_string_pretty(&value)?,
+            OutputFormat::Yaml => serde_yaml::to_string(&value)?,
+            OutputFormat::Table => table(&value),
+            OutputFormat::Debug => format!("{:#?}", response),
+        };
+        write_line(rendered.trim_end())?;
+        Ok(value)
+    }
+}
+
+/// Writes a line to stdout, and exits quietly once stdout is closed, e.g. by `head`.
+pub(crate) fn write_line(line: &str) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    match writeln!(stdout, "{}", line).and_then(|()| stdout.flush()) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => Ok(result?),
+    }
+}
+
+/// The response as JSON, with embedded JSON documents expanded.
+pub(crate) fn to_json<T: Serialize>(response: &T) -> Result<Value> {
+    let mut value = serde_json::to_value(response)?;
+    expand_embedded_json(&mut value);
+    Ok(value)
+}
+
+/// Replaces string fields that contain JSON documents, such as job variables, with the documents.
+pub(crate) fn expand_embedded_json(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if let Value::String(json) = field {
+                    if EMBEDDED_JSON_FIELDS.contains(&name.as_str()) {
+                        *field = match json.trim() {
+                            "" => Value::Object(Default::default()),
+                            json => match serde_json::from_str(json) {
+                                Ok(document) => document,
+                                Err(_) => continue,
+                            },
+                        };
+                        continue;
+                    }
+                }
+                expand_embedded_json(field);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(expand_embedded_json),
+        _ => {}
+    }
+}
+
+/// Renders scalar fields as a two-column table, followed by a table per list of objects.
+fn table(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => {
+            let mut properties = vec![];
+            let mut sections = vec![];
+            for (name, field) in fields {
+                match field {
+                    Value::Array(items) if is_list_of_objects(items) => {
+                        sections.push(format!("{}:\n{}", name, rows(items)))
+                    }
+                    field => properties.push(vec![name.clone(), cell(field)]),
+                }
+            }
+            let mut tables = vec![];
+            if !properties.is_empty() {
+                tables.push(grid(&["FIELD".to_owned(), "VALUE".to_owned()], &properties));
+            }
+            tables.extend(sections);
+            tables.join("\n")
+        }
+        Value::Array(items) if is_list_of_objects(items) => rows(items),
+        value => cell(value),
+    }
+}
+
+fn is_list_of_objects(items: &[Value]) -> bool {
+    !items.is_empty() && items.iter().all(Value::is_object)
+}
+
+/// One row per object, with a column per field of any of them.
+fn rows(items: &[Value]) -> String {
+    let mut columns: Vec<String> = vec![];
+    for item in items {
+        for name in item
+            .as_object()
+            .into_iter()
+            .flat_map(|fields| fields.keys())
+        {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+    }
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|column| item.get(column).map(cell).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    let header: Vec<String> = columns.iter().map(|column| column.to_uppercase()).collect();
+    grid(&header, &rows)
+}
+
+fn grid(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        padded.join("  ").trim_end().to_owned() + "\n"
+    };
+    let mut rendered = line(header);
+    for row in rows {
+        rendered.push_str(&line(row));
+    }
+    rendered
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn expands_embedded_json() {
+        let mut response = json!({
+            "jobs": [{
+                "key": 1,
+                "variables": r#"{"order":{"id":"o-1"}}"#,
+                "customHeaders": "",
+            }],
+            "variables": "not json",
+            "name": r#"{"kept":"as string"}"#,
+        });
+        expand_embedded_json(&mut response);
+        assert_eq!(
+            response,
+            json!({
+                "jobs": [{
+                    "key": 1,
+                    "variables": { "order": { "id": "o-1" } },
+                    "customHeaders": {},
+                }],
+                "variables": "not json",
+                "name": r#"{"kept":"as string"}"#,
+            })
+        );
+    }
+
+    #[test]
+    fn renders_objects_as_tables() {
+        let response = json!({
+            "key": 2251799813685249_i64,
+            "bpmnProcessId": "order",
+            "tenant": null,
+        });
+        assert_eq!(
+            table(&response),
+            "\
+FIELD          VALUE
+bpmnProcessId  order
+key            2251799813685249
+tenant
+"
+        );
+    }
+
+    #[test]
+    fn renders_lists_as_tables() {
+        let response = json!({
+            "clusterSize": 1,
+            "brokers": [
+                { "nodeId": 0, "host": "broker-0", "partitions": [{ "partitionId": 1 }] },
+                { "nodeId": 1, "version": "8.2.0" },
+            ],
+        });
+        assert_eq!(
+            table(&response),
+            "\
+FIELD        VALUE
+clusterSize  1
+
+brokers:
+HOST      NODEID  PARTITIONS           VERSION
+broker-0  0       [{\"partitionId\":1}]
+          1                            8.2.0
+"
+        );
+        assert_eq!(table(&json!([])), "[]");
+    }
+}
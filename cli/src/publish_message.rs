@@ -9,7 +9,7 @@ use zeebe_client::{
     ZeebeClient,
 };
 
-use crate::ExecuteZeebeCommand;
+use crate::{variables::VariablesArgs, ExecuteZeebeCommand};
 
 #[derive(Args, Clone, Debug)]
 pub(crate) struct PublishMessageArgs {
@@ -19,21 +19,23 @@ pub(crate) struct PublishMessageArgs {
     correlation_key: String,
     #[arg(long, required = false)]
     message_id: String,
-    #[arg(long, required = false, default_value = "")]
-    variables: String,
+    #[command(flatten)]
+    variables: VariablesArgs,
     #[arg(long, required = false, default_value_t = -1)]
     ttl: i64, // todo: should be duration
 }
 
-impl From<&PublishMessageArgs> for PublishMessageRequest {
-    fn from(args: &PublishMessageArgs) -> Self {
-        PublishMessageRequest {
+impl TryFrom<&PublishMessageArgs> for PublishMessageRequest {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: &PublishMessageArgs) -> Result<Self> {
+        Ok(PublishMessageRequest {
             name: args.name.to_owned(),
             correlation_key: args.correlation_key.to_owned(),
             time_to_live: args.ttl,
             message_id: args.message_id.to_owned(),
-            variables: args.variables.to_owned(),
-        }
+            variables: args.variables.to_json()?,
+        })
     }
 }
 
@@ -44,7 +46,7 @@ impl ExecuteZeebeCommand for PublishMessageArgs {
     #[tracing::instrument(skip(client))]
     async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
         let args = &self;
-        let request: PublishMessageRequest = args.into();
+        let request: PublishMessageRequest = args.try_into()?;
         Ok(client.publish_message(request).await?.into_inner())
     }
 }
@@ -1,9 +1,9 @@
 use std::path::PathBuf;
 
-use crate::ExecuteZeebeCommand;
+use crate::{variables::VariablesArgs, ExecuteZeebeCommand};
 use async_trait::async_trait;
 use clap::Args;
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
 
 use zeebe_client::{
     api::{SetVariablesRequest, SetVariablesResponse},
@@ -17,23 +17,32 @@ pub(crate) struct SetVariablesArgs {
     element_instance_key: i64,
     #[arg(short, long)]
     local: bool,
-    #[arg(long, group = "value")]
+    #[command(flatten)]
+    variables: VariablesArgs,
+    /// Deprecated, use `--variables @<path>`
+    #[arg(long, hide = true, conflicts_with_all = ["variables", "json"])]
     path: Option<PathBuf>,
-    #[arg(long, group = "value")]
+    /// Deprecated, use `--variables <json>`
+    #[arg(long, hide = true, conflicts_with = "variables")]
     json: Option<String>,
 }
 
 impl TryFrom<SetVariablesArgs> for SetVariablesRequest {
     type Error = color_eyre::Report;
 
-    fn try_from(args: SetVariablesArgs) -> Result<SetVariablesRequest, Self::Error> {
-        let variables = if let Some(path) = &args.path {
-            std::fs::read_to_string(path)?
-        } else if let Some(json) = args.json {
-            json
-        } else {
-            unreachable!("Should be a path or a JSON string")
-        };
+    fn try_from(mut args: SetVariablesArgs) -> Result<SetVariablesRequest, Self::Error> {
+        if let Some(path) = args.path {
+            tracing::warn!("--path is deprecated, use --variables @<path>");
+            args.variables.variables = Some(format!("@{}", path.display()));
+        }
+        if let Some(json) = args.json {
+            tracing::warn!("--json is deprecated, use --variables <json>");
+            args.variables.variables = Some(json);
+        }
+        let variables = args.variables.to_json()?;
+        if variables.is_empty() {
+            return Err(eyre!("Needs --variables or --var"));
+        }
         Ok(Self {
             element_instance_key: args.element_instance_key,
             variables,
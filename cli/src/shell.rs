@@ -0,0 +1,348 @@
+use std::{ffi::OsStr, path::Path};
+
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser};
+use color_eyre::eyre::{eyre, Result};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    history::DefaultHistory,
+    Context, Editor, Helper, Highlighter, Hinter, Validator,
+};
+use serde_json::{Map, Value};
+use zeebe_client::ZeebeClient;
+
+use crate::{
+    output::{OutputFormat, EMBEDDED_JSON_FIELDS},
+    Commands,
+};
+
+const PROMPT: &str = "zeebe> ";
+
+/// Prefix of the placeholders for values of previous responses.
+const LAST: &str = "$last.";
+
+/// Arguments that read stdin when given `-`, or `<scopeId>=-` in modify-process-instance.
+const STDIN_ARGS: [&str; 2] = ["variables", "json"];
+
+#[derive(Args)]
+pub(crate) struct ShellArgs {}
+
+/// A line of input, which takes the same commands as the CLI, but no connection options.
+#[derive(Parser)]
+#[command(name = "zeebe", no_binary_name = true)]
+pub(crate) struct Line {
+    #[arg(long, value_enum, global = true)]
+    pub(crate) output: Option<OutputFormat>,
+    #[command(subcommand)]
+    pub(crate) command: Commands,
+}
+
+impl ShellArgs {
+    /// Reads commands until `exit` or end of input and runs them with `client`. The history is
+    /// kept next to the config file.
+    pub(crate) async fn execute(
+        self,
+        mut client: ZeebeClient,
+        output: OutputFormat,
+        config_path: &Path,
+    ) -> Result<()> {
+        let mut editor = Editor::<ShellHelper, DefaultHistory>::new()?;
+        editor.set_helper(Some(ShellHelper::new()));
+        let history = config_path.with_file_name("history");
+        // there is no history before the first session
+        let _ = editor.load_history(&history);
+
+        let mut last = Map::new();
+        loop {
+            let line = match tokio::task::block_in_place(|| editor.readline(PROMPT)) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line)?;
+            if line == "exit" || line == "quit" {
+                break;
+            }
+            if let Err(err) = run_line(line, &mut client, output, &mut last).await {
+                eprintln!("Error: {:#}", err);
+            }
+        }
+
+        if let Some(dir) = history.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        editor.save_history(&history)?;
+        Ok(())
+    }
+}
+
+async fn run_line(
+    line: &str,
+    client: &mut ZeebeClient,
+    output: OutputFormat,
+    last: &mut Map<String, Value>,
+) -> Result<()> {
+    let words = shlex::split(line).ok_or_else(|| eyre!("Unbalanced quotes"))?;
+    let words = words
+        .iter()
+        .map(|word| substitute(word, last))
+        .collect::<Result<Vec<_>>>()?;
+    let parsed = Line::command()
+        .try_get_matches_from(words)
+        .and_then(|matches| Ok((Line::from_arg_matches(&matches)?, matches)));
+    let (line, matches) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            // also prints the help when asked for
+            err.print()?;
+            return Ok(());
+        }
+    };
+    check_runnable(&matches, &line.command, "the shell")?;
+    let output = line.output.unwrap_or(output);
+    let response = crate::execute(line.command, client, Some(output)).await?;
+    remember(&response, last);
+    Ok(())
+}
+
+/// Rejects what can't run `within` the shell or a batch: commands that don't use the connection
+/// or run until interrupted, and arguments that read stdin, which holds the terminal or the batch.
+pub(crate) fn check_runnable(matches: &ArgMatches, command: &Commands, within: &str) -> Result<()> {
+    if let Commands::Work(_)
+    | Commands::Batch(_)
+    | Commands::Shell(_)
+    | Commands::Profile(_)
+    | Commands::Completions(_)
+    | Commands::Man(_) = command
+    {
+        let name = matches.subcommand_name().unwrap_or_default();
+        return Err(eyre!("{} is not available in {}", name, within));
+    }
+    if let Some((_, args)) = matches.subcommand() {
+        for id in STDIN_ARGS {
+            if let Ok(Some(mut values)) = args.try_get_raw(id) {
+                let stdin = |value: &OsStr| value == "-" || value.to_string_lossy().ends_with("=-");
+                if values.any(stdin) {
+                    return Err(eyre!("--{} can't read stdin in {}", id, within));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces `$last.<field>` with the latest value of a response field of that name.
+fn substitute(word: &str, last: &Map<String, Value>) -> Result<String> {
+    let mut substituted = String::new();
+    let mut rest = word;
+    while let Some(start) = rest.find(LAST) {
+        substituted.push_str(&rest[..start]);
+        let placeholder = &rest[start + LAST.len()..];
+        let end = placeholder
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(placeholder.len());
+        let field = &placeholder[..end];
+        match last.get(field) {
+            Some(Value::String(value)) => substituted.push_str(value),
+            Some(value) => substituted.push_str(&value.to_string()),
+            None => return Err(eyre!("No value for {}{}", LAST, field)),
+        }
+        rest = &placeholder[end..];
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+/// Records the scalar fields of a response. Of fields that occur several times, e.g. the keys of
+/// activated jobs, the first is recorded. Job variables and headers are left out, so that they
+/// don't shadow the fields of the response.
+fn remember(response: &Value, last: &mut Map<String, Value>) {
+    fn collect(value: &Value, fields: &mut Map<String, Value>) {
+        match value {
+            Value::Object(object) => {
+                for (name, field) in object {
+                    if EMBEDDED_JSON_FIELDS.contains(&name.as_str()) {
+                        continue;
+                    }
+                    match field {
+                        Value::Object(_) | Value::Array(_) => collect(field, fields),
+                        Value::Null => {}
+                        scalar => {
+                            fields.entry(name.clone()).or_insert_with(|| scalar.clone());
+                        }
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, fields)),
+            _ => {}
+        }
+    }
+    let mut fields = Map::new();
+    collect(response, &mut fields);
+    last.extend(fields);
+}
+
+/// Completes command names and their options.
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ShellHelper {
+    commands: Vec<(String, Vec<String>)>,
+}
+
+impl ShellHelper {
+    fn new() -> ShellHelper {
+        let mut line = Line::command();
+        // adds the global and help options to the commands
+        line.build();
+        let commands = line
+            .get_subcommands()
+            .map(|command| {
+                let options = command
+                    .get_arguments()
+                    .filter_map(|arg| arg.get_long())
+                    .map(|long| format!("--{}", long))
+                    .collect();
+                (command.get_name().to_owned(), options)
+            })
+            .collect();
+        ShellHelper { commands }
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+        let word = &line[start..];
+        let candidates: Vec<&str> = match line[..start].split_whitespace().next() {
+            None => self
+                .commands
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .chain(["exit", "quit"])
+                .collect(),
+            Some(command) if word.starts_with('-') => self
+                .commands
+                .iter()
+                .filter(|(name, _)| name == command)
+                .flat_map(|(_, options)| options.iter().map(String::as_str))
+                .collect(),
+            Some(_) => vec![],
+        };
+        let pairs = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .map(|candidate| Pair {
+                display: candidate.to_owned(),
+                replacement: candidate.to_owned(),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn fields(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(fields) => fields,
+            other => panic!("not an object: {}", other),
+        }
+    }
+
+    fn check(words: &[&str]) -> Result<()> {
+        let matches = Line::command().try_get_matches_from(words).unwrap();
+        let line = Line::from_arg_matches(&matches).unwrap();
+        check_runnable(&matches, &line.command, "the shell")
+    }
+
+    #[test]
+    fn rejects_what_cannot_run_in_the_shell() {
+        assert!(check(&["status"]).is_ok());
+        assert!(check(&["complete-job", "1", "--variables", "{}"]).is_ok());
+        let err = |words| check(words).unwrap_err().to_string();
+        assert_eq!(
+            err(&["work", "payment", "--exec", "true"]),
+            "work is not available in the shell"
+        );
+        assert_eq!(err(&["shell"]), "shell is not available in the shell");
+        assert_eq!(
+            err(&["complete-job", "1", "--variables", "-"]),
+            "--variables can't read stdin in the shell"
+        );
+        assert_eq!(
+            err(&[
+                "modify-process-instance",
+                "1",
+                "--activate",
+                "review",
+                "--variables",
+                "review=-"
+            ]),
+            "--variables can't read stdin in the shell"
+        );
+        assert_eq!(
+            err(&["set-variables", "--element-instance-key", "1", "--json=-"]),
+            "--json can't read stdin in the shell"
+        );
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let last = fields(json!({ "key": 2251799813685249_i64, "bpmnProcessId": "order" }));
+        assert_eq!(substitute("$last.key", &last).unwrap(), "2251799813685249");
+        assert_eq!(
+            substitute("--process-id=$last.bpmnProcessId", &last).unwrap(),
+            "--process-id=order"
+        );
+        assert_eq!(
+            substitute("$last.bpmnProcessId/$last.key.", &last).unwrap(),
+            "order/2251799813685249."
+        );
+        assert_eq!(substitute("plain", &last).unwrap(), "plain");
+    }
+
+    #[test]
+    fn fails_on_unknown_placeholders() {
+        let err = substitute("$last.missing", &Map::new()).unwrap_err();
+        assert_eq!(err.to_string(), "No value for $last.missing");
+    }
+
+    #[test]
+    fn remembers_the_first_scalar_of_each_field() {
+        let mut last = fields(json!({ "key": 1, "retries": 3 }));
+        remember(
+            &json!({
+                "jobs": [
+                    {
+                        "customHeaders": { "key": "header", "type": "header" },
+                        "key": 10,
+                        "type": "payment",
+                        "variables": { "orderId": "o-1", "retries": 0 },
+                    },
+                    { "key": 11, "type": "payment", "worker": null },
+                ],
+            }),
+            &mut last,
+        );
+        assert_eq!(
+            Value::Object(last),
+            json!({ "key": 10, "retries": 3, "type": "payment" })
+        );
+    }
+}
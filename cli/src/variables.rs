@@ -0,0 +1,173 @@
+use std::{io::Read, path::Path};
+
+use clap::Args;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde_json::{Map, Value};
+
+// Variables of a command, given inline, from a file or stdin, or as single `--var` pairs. Not a
+// doc comment, as clap would copy it into the about of the commands that flatten this.
+#[derive(Args, Clone, Debug, Default)]
+pub(crate) struct VariablesArgs {
+    /// Variables as a JSON object, `@<path>` to read a JSON or YAML file, or `-` to read stdin
+    #[arg(long)]
+    pub(crate) variables: Option<String>,
+    /// A variable as `<name>=<value>`, can be repeated. Values that are valid JSON, such as
+    /// numbers, booleans or objects, are used as such, anything else as a string
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    vars: Vec<(String, Value)>,
+}
+
+impl VariablesArgs {
+    /// The variables as a JSON object, or an empty string if none were given.
+    pub(crate) fn to_json(&self) -> Result<String> {
+        Ok(self
+            .to_object()?
+            .map(|variables| Value::Object(variables).to_string())
+            .unwrap_or_default())
+    }
+
+    fn to_object(&self) -> Result<Option<Map<String, Value>>> {
+        let mut variables = self.variables.as_deref().map(parse).transpose()?;
+        if !self.vars.is_empty() {
+            variables
+                .get_or_insert_with(Map::new)
+                .extend(self.vars.iter().cloned());
+        }
+        Ok(variables)
+    }
+}
+
+/// Parses variables given as a JSON object, `@<path>` of a JSON or YAML file, or `-` for stdin.
+pub(crate) fn parse(variables: &str) -> Result<Map<String, Value>> {
+    match variables {
+        "-" => {
+            let mut document = String::new();
+            std::io::stdin()
+                .read_to_string(&mut document)
+                .wrap_err("failed to read variables from stdin")?;
+            object(serde_yaml::from_str(&document).wrap_err("invalid variables on stdin")?)
+        }
+        variables => match variables.strip_prefix('@') {
+            Some(path) => object(read_file(Path::new(path))?),
+            None => {
+                object(serde_json::from_str(variables).wrap_err("invalid JSON in --variables")?)
+            }
+        },
+    }
+}
+
+fn read_file(path: &Path) -> Result<Value> {
+    let document = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read variables from {}", path.display()))?;
+    // YAML is a superset of JSON, so this reads both
+    serde_yaml::from_str(&document)
+        .wrap_err_with(|| format!("invalid variables in {}", path.display()))
+}
+
+fn object(variables: Value) -> Result<Map<String, Value>> {
+    match variables {
+        Value::Object(variables) => Ok(variables),
+        other => Err(eyre!("variables must be a JSON object, got {}", other)),
+    }
+}
+
+fn parse_var(var: &str) -> Result<(String, Value), String> {
+    let (name, value) = var
+        .split_once('=')
+        .ok_or_else(|| "expected <name>=<value>".to_owned())?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+    Ok((name.to_owned(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn args(variables: Option<&str>, vars: &[&str]) -> VariablesArgs {
+        VariablesArgs {
+            variables: variables.map(str::to_owned),
+            vars: vars.iter().map(|var| parse_var(var).unwrap()).collect(),
+        }
+    }
+
+    fn to_value(args: &VariablesArgs) -> Value {
+        serde_json::from_str(&args.to_json().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_vars() {
+        assert_eq!(
+            parse_var("amount=42").unwrap(),
+            ("amount".to_owned(), json!(42))
+        );
+        assert_eq!(
+            parse_var("express=true").unwrap(),
+            ("express".to_owned(), json!(true))
+        );
+        assert_eq!(
+            parse_var(r#"address={"city":"Berlin"}"#).unwrap(),
+            ("address".to_owned(), json!({ "city": "Berlin" }))
+        );
+        assert_eq!(
+            parse_var("name=Jane").unwrap(),
+            ("name".to_owned(), json!("Jane"))
+        );
+        assert_eq!(
+            parse_var("url=a=b").unwrap(),
+            ("url".to_owned(), json!("a=b"))
+        );
+        assert_eq!(
+            parse_var("empty=").unwrap(),
+            ("empty".to_owned(), json!(""))
+        );
+        assert!(parse_var("amount").is_err());
+    }
+
+    #[test]
+    fn is_empty_without_variables() {
+        assert_eq!(args(None, &[]).to_json().unwrap(), "");
+    }
+
+    #[test]
+    fn takes_inline_json() {
+        let args = args(Some(r#"{"orderId":"o-1"}"#), &[]);
+        assert_eq!(to_value(&args), json!({ "orderId": "o-1" }));
+    }
+
+    #[test]
+    fn overrides_variables_with_vars() {
+        let given = args(
+            Some(r#"{"orderId":"o-1","amount":1}"#),
+            &["amount=42", "express=true"],
+        );
+        assert_eq!(
+            to_value(&given),
+            json!({ "orderId": "o-1", "amount": 42, "express": true })
+        );
+        assert_eq!(
+            to_value(&args(None, &["amount=42"])),
+            json!({ "amount": 42 })
+        );
+    }
+
+    #[test]
+    fn reads_yaml_files() {
+        let path = std::env::temp_dir().join(format!("variables-{}.yaml", std::process::id()));
+        std::fs::write(&path, "orderId: o-1\nitems: [1, 2]\n").unwrap();
+        let variables = format!("@{}", path.display());
+        let value = to_value(&args(Some(&variables), &[]));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(value, json!({ "orderId": "o-1", "items": [1, 2] }));
+    }
+
+    #[test]
+    fn rejects_variables_that_are_no_object() {
+        assert!(args(Some("[1, 2]"), &[]).to_json().is_err());
+        assert!(args(Some("{"), &[]).to_json().is_err());
+        assert!(args(Some("@/nonexistent/variables.json"), &[])
+            .to_json()
+            .is_err());
+    }
+}
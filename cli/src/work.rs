@@ -0,0 +1,298 @@
+use std::{
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use clap::Args;
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::{io::AsyncWriteExt, process::Command};
+use zeebe_client::ZeebeClient;
+use zeebe_worker::{DeadlinePolicy, Job, JobOutcome, WorkerBuilder};
+
+use crate::{output::expand_embedded_json, ExecuteZeebeCommand};
+
+#[derive(Args, Debug)]
+pub(crate) struct WorkArgs {
+    job_type: String,
+
+    /// Command to run for each job, with the job as JSON on stdin. Exit code 0 completes the job
+    /// with the JSON object printed to stdout as variables, the error exit code throws the BPMN
+    /// error printed to stdout as `{"errorCode": ..., "errorMessage": ...}`, and any other exit
+    /// code fails the job with stderr as error message
+    #[arg(short, long)]
+    exec: String,
+    #[arg(long, default_value = "zeebe-cli")]
+    name: String,
+    /// Number of jobs handled at the same time
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// How long jobs stay locked to this worker, in ms. Commands still running at the deadline
+    /// are killed
+    #[arg(long, default_value_t = 5 * 60 * 1000)]
+    timeout: u64, // todo: should be duration
+    /// Pause between polls that returned no jobs, in ms
+    #[arg(long, default_value_t = 100)]
+    poll_interval: u64,
+    /// Back off before failed jobs are retried, in ms
+    #[arg(long, default_value_t = 0)]
+    retry_back_off: u64,
+    #[arg(long, default_value_t = 3)]
+    error_exit_code: i32,
+    #[arg(long)]
+    fetch_variables: Vec<String>,
+}
+
+/// Number of jobs handled with each outcome, printed once the worker is stopped.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkSummary {
+    completed: u64,
+    failed: u64,
+    errors_thrown: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    completed: AtomicU64,
+    failed: AtomicU64,
+    errors_thrown: AtomicU64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BpmnError {
+    error_code: String,
+    #[serde(default)]
+    error_message: String,
+}
+
+struct Exec {
+    command: String,
+    error_exit_code: i32,
+    retry_back_off: Duration,
+}
+
+#[async_trait]
+impl ExecuteZeebeCommand for WorkArgs {
+    type Output = WorkSummary;
+
+    #[tracing::instrument(skip(client))]
+    async fn execute(self, client: &mut ZeebeClient) -> Result<Self::Output> {
+        let exec = Arc::new(Exec {
+            command: self.exec,
+            error_exit_code: self.error_exit_code,
+            retry_back_off: Duration::from_millis(self.retry_back_off),
+        });
+        let counters = Arc::new(Counters::default());
+        let handler_counters = counters.clone();
+        let worker = WorkerBuilder::new(client.clone(), self.job_type)
+            .name(self.name)
+            .max_jobs_active(self.concurrency)
+            .timeout(Duration::from_millis(self.timeout))
+            .poll_interval(Duration::from_millis(self.poll_interval))
+            .fetch_variables(self.fetch_variables)
+            .deadline_policy(DeadlinePolicy {
+                abort_handler: true,
+                ..Default::default()
+            })
+            .handler(move |job: Job| {
+                let exec = exec.clone();
+                let counters = handler_counters.clone();
+                async move {
+                    let outcome = run(&exec, job).await;
+                    let counter = match &outcome {
+                        JobOutcome::Complete { .. } => &counters.completed,
+                        JobOutcome::Fail { .. } | JobOutcome::Overloaded { .. } => &counters.failed,
+                        JobOutcome::ThrowError { .. } => &counters.errors_thrown,
+                    };
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    outcome
+                }
+            })
+            .build()?;
+
+        let shutdown = worker.shutdown_token();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                tracing::info!("stopping, waiting for running commands");
+                shutdown.cancel();
+            }
+        });
+        worker.run().await?;
+
+        Ok(WorkSummary {
+            completed: counters.completed.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+            errors_thrown: counters.errors_thrown.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// Runs the command for a job. The command is killed if the returned future is dropped, which
+/// happens when the job deadline is reached.
+async fn run(exec: &Exec, job: Job) -> JobOutcome {
+    let mut input = match serde_json::to_value(job.into_inner()) {
+        Ok(input) => input,
+        Err(err) => return JobOutcome::fail(format!("failed to serialize job: {}", err)),
+    };
+    expand_embedded_json(&mut input);
+
+    let mut child = match shell(&exec.command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => return JobOutcome::fail(format!("failed to run {}: {}", exec.command, err)),
+    };
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let write_input = async move {
+        // commands that don't read the job close stdin early, which is fine
+        let _ = stdin.write_all(input.to_string().as_bytes()).await;
+    };
+    let (_, output) = tokio::join!(write_input, child.wait_with_output());
+    let output = match output {
+        Ok(output) => output,
+        Err(err) => return JobOutcome::fail(format!("failed to run {}: {}", exec.command, err)),
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = stdout.trim();
+    match output.status.code() {
+        Some(0) if stdout.is_empty() => JobOutcome::complete(),
+        Some(0) => match serde_json::from_str::<Map<String, Value>>(stdout) {
+            Ok(_) => JobOutcome::Complete {
+                variables: stdout.to_owned(),
+            },
+            Err(err) => JobOutcome::fail(format!("command printed invalid variables: {}", err)),
+        },
+        Some(code) if code == exec.error_exit_code => {
+            match serde_json::from_str::<BpmnError>(stdout) {
+                Ok(error) => JobOutcome::ThrowError {
+                    error_code: error.error_code,
+                    error_message: error.error_message,
+                },
+                Err(err) => JobOutcome::fail(format!("command printed an invalid error: {}", err)),
+            }
+        }
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error_message = match stderr.trim() {
+                "" => format!("command exited with {}", output.status),
+                stderr => stderr.to_owned(),
+            };
+            JobOutcome::Fail {
+                error_message,
+                retries: None,
+                retry_back_off: exec.retry_back_off,
+                variables: String::new(),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use serde_json::json;
+    use zeebe_worker::testing::TestJob;
+
+    use super::*;
+
+    async fn outcome(command: &str) -> JobOutcome {
+        let exec = Exec {
+            command: command.to_owned(),
+            error_exit_code: 3,
+            retry_back_off: Duration::from_secs(5),
+        };
+        let job = TestJob::new("work").variables(&json!({ "orderId": "o-1" }));
+        run(&exec, job.job()).await
+    }
+
+    #[tokio::test]
+    async fn completes_with_the_variables_printed_to_stdout() {
+        assert_eq!(
+            outcome(r#"grep -q '"variables":{"orderId":"o-1"}' && echo '{"charged": true}'"#).await,
+            JobOutcome::Complete {
+                variables: r#"{"charged": true}"#.to_owned()
+            }
+        );
+        assert_eq!(outcome("true").await, JobOutcome::complete());
+    }
+
+    #[tokio::test]
+    async fn fails_on_invalid_variables() {
+        match outcome("echo '[1, 2]'").await {
+            JobOutcome::Fail { error_message, .. } => {
+                assert!(error_message.starts_with("command printed invalid variables"))
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn throws_the_error_printed_on_the_error_exit_code() {
+        assert_eq!(
+            outcome(r#"echo '{"errorCode": "OUT_OF_STOCK", "errorMessage": "none left"}'; exit 3"#)
+                .await,
+            JobOutcome::throw_error("OUT_OF_STOCK", "none left")
+        );
+        match outcome("echo 'out of stock'; exit 3").await {
+            JobOutcome::Fail { error_message, .. } => {
+                assert!(error_message.starts_with("command printed an invalid error"))
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_with_stderr_on_other_exit_codes() {
+        assert_eq!(
+            outcome("echo 'card declined' >&2; exit 1").await,
+            JobOutcome::Fail {
+                error_message: "card declined".to_owned(),
+                retries: None,
+                retry_back_off: Duration::from_secs(5),
+                variables: String::new(),
+            }
+        );
+        match outcome("exit 2").await {
+            JobOutcome::Fail { error_message, .. } => {
+                assert_eq!(error_message, "command exited with exit status: 2")
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn kills_the_command_once_dropped() {
+        let marker = std::env::temp_dir().join(format!("work-killed-{}", std::process::id()));
+        let command = format!("sleep 1; touch {}", marker.display());
+        let timed_out = tokio::time::timeout(Duration::from_millis(200), outcome(&command)).await;
+        assert!(timed_out.is_err());
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+}
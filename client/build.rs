@@ -1,6 +1,19 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // the server side is only used to run stub gateways, e.g. in the worker tests
     tonic_build::configure()
-        .build_server(false)
+        .build_server(std::env::var_os("CARGO_FEATURE_SERVER").is_some())
+        .type_attribute(
+            ".",
+            r#"#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "camelCase"))]"#,
+        )
+        .field_attribute(
+            "Partition.role",
+            r#"#[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_enums::role"))]"#,
+        )
+        .field_attribute(
+            "Partition.health",
+            r#"#[cfg_attr(feature = "serde", serde(serialize_with = "crate::serde_enums::health"))]"#,
+        )
         .compile(&["proto/gateway.proto"], &["proto/"])?;
     Ok(())
 }
@@ -7,7 +7,7 @@ use thiserror::Error;
 use tonic::{metadata::MetadataValue, service::Interceptor};
 use tracing::instrument;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OAuth2Config {
     pub client_id: String,
     pub client_secret: String,
@@ -15,7 +15,7 @@ pub struct OAuth2Config {
     pub audience: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OAuth2Provider {
     client: BasicClient,
     config: OAuth2Config,
@@ -53,6 +53,7 @@ impl OAuth2Provider {
     }
 }
 
+#[derive(Clone)]
 pub struct AuthInterceptor {
     auth: Option<OAuth2Provider>,
 }
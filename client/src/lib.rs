@@ -1,16 +1,19 @@
 pub mod auth;
+pub mod process_instance;
 
 use auth::{AuthInterceptor, OAuth2Config};
 use generated_api::gateway_client::GatewayClient;
 use oauth2::url::ParseError;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tracing::instrument;
 
 use tonic::{
     codegen::http::{self},
-    transport::{self, Channel, ClientTlsConfig, Uri},
+    transport::{self, Certificate, Channel, ClientTlsConfig, Identity, Uri},
 };
 
+#[allow(clippy::doc_lazy_continuation)]
 mod generated_api {
     tonic::include_proto!("gateway_protocol");
 }
@@ -19,10 +22,38 @@ pub mod api {
     pub use super::generated_api::*;
 }
 
-#[derive(Debug, Clone)]
+/// Serializes enum fields, which prost stores as `i32`, by their names.
+#[cfg(feature = "serde")]
+mod serde_enums {
+    use serde::Serializer;
+
+    use crate::api::partition::{PartitionBrokerHealth, PartitionBrokerRole};
+
+    pub(crate) fn role<S: Serializer>(role: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+        match PartitionBrokerRole::from_i32(*role) {
+            Some(role) => serializer.serialize_str(role.as_str_name()),
+            None => serializer.serialize_i32(*role),
+        }
+    }
+
+    pub(crate) fn health<S: Serializer>(health: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+        match PartitionBrokerHealth::from_i32(*health) {
+            Some(health) => serializer.serialize_str(health.as_str_name()),
+            None => serializer.serialize_i32(*health),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Connection {
     pub insecure: bool,
     pub addr: String,
+    /// PEM file with the CA certificate to verify the gateway with, instead of the system's roots
+    pub ca_certificate: Option<PathBuf>,
+    /// PEM file with a certificate to authenticate the client with, together with `client_key`
+    pub client_certificate: Option<PathBuf>,
+    /// PEM file with the private key of `client_certificate`
+    pub client_key: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -39,6 +70,13 @@ pub enum ConnectionError {
     Http(#[from] http::Error),
     #[error(transparent)]
     Oauth2(#[from] ParseError),
+    #[error("failed to read {path}: {source}")]
+    Pem {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("a client certificate and key must be given together")]
+    IncompleteIdentity,
 }
 
 pub type ZeebeClient =
@@ -54,7 +92,7 @@ pub async fn connect(
             true => "http",
             false => "https",
         })
-        .authority(conn.addr)
+        .authority(conn.addr.as_str())
         .path_and_query("")
         .build()?;
     let interceptor = match auth {
@@ -65,10 +103,32 @@ pub async fn connect(
     let channel = if conn.insecure {
         Channel::builder(uri)
     } else {
-        Channel::builder(uri).tls_config(ClientTlsConfig::new())?
+        Channel::builder(uri).tls_config(tls_config(&conn)?)?
     };
     Ok(api::gateway_client::GatewayClient::with_interceptor(
         channel.connect().await?,
         interceptor,
     ))
 }
+
+fn tls_config(conn: &Connection) -> Result<ClientTlsConfig, ConnectionError> {
+    let mut config = ClientTlsConfig::new();
+    if let Some(path) = &conn.ca_certificate {
+        config = config.ca_certificate(Certificate::from_pem(read_pem(path)?));
+    }
+    match (&conn.client_certificate, &conn.client_key) {
+        (Some(certificate), Some(key)) => {
+            config = config.identity(Identity::from_pem(read_pem(certificate)?, read_pem(key)?));
+        }
+        (None, None) => {}
+        _ => return Err(ConnectionError::IncompleteIdentity),
+    }
+    Ok(config)
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, ConnectionError> {
+    std::fs::read(path).map_err(|source| ConnectionError::Pem {
+        path: path.to_owned(),
+        source,
+    })
+}
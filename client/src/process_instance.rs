@@ -0,0 +1,193 @@
+//! Typed builders for process instance requests.
+
+use crate::api::{
+    modify_process_instance_request::{
+        ActivateInstruction, TerminateInstruction, VariableInstruction,
+    },
+    CreateProcessInstanceRequest, ModifyProcessInstanceRequest,
+    ProcessInstanceCreationStartInstruction,
+};
+
+/// Builds a [`CreateProcessInstanceRequest`] for a process given either by BPMN process id or by
+/// process definition key.
+///
+/// ```
+/// use zeebe_client::process_instance::CreateProcessInstance;
+///
+/// let request = CreateProcessInstance::with_bpmn_process_id("order")
+///     .version(3)
+///     .variables(r#"{"orderId":"o-1"}"#)
+///     .start_before("ship")
+///     .build();
+/// assert_eq!(request.start_instructions[0].element_id, "ship");
+/// ```
+#[derive(Debug, Clone)]
+pub struct CreateProcessInstance {
+    request: CreateProcessInstanceRequest,
+}
+
+impl CreateProcessInstance {
+    /// Creates an instance of the latest version of the process, unless a
+    /// [`version`](Self::version) is given.
+    pub fn with_bpmn_process_id(bpmn_process_id: impl Into<String>) -> CreateProcessInstance {
+        CreateProcessInstance {
+            request: CreateProcessInstanceRequest {
+                bpmn_process_id: bpmn_process_id.into(),
+                version: -1,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn with_process_definition_key(process_definition_key: i64) -> CreateProcessInstance {
+        CreateProcessInstance {
+            request: CreateProcessInstanceRequest {
+                process_definition_key,
+                version: -1,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Selects a version of the process given by BPMN process id.
+    pub fn version(mut self, version: i32) -> Self {
+        self.request.version = version;
+        self
+    }
+
+    /// Sets the variables of the instance, which must be a JSON document with an object at the
+    /// root.
+    pub fn variables(mut self, variables: impl Into<String>) -> Self {
+        self.request.variables = variables.into();
+        self
+    }
+
+    /// Starts the instance before the element with id `element_id` instead of at the default
+    /// start event. Can be repeated to start at several elements.
+    pub fn start_before(mut self, element_id: impl Into<String>) -> Self {
+        self.request
+            .start_instructions
+            .push(ProcessInstanceCreationStartInstruction {
+                element_id: element_id.into(),
+            });
+        self
+    }
+
+    pub fn build(self) -> CreateProcessInstanceRequest {
+        self.request
+    }
+}
+
+impl From<CreateProcessInstance> for CreateProcessInstanceRequest {
+    fn from(builder: CreateProcessInstance) -> Self {
+        builder.build()
+    }
+}
+
+/// Builds a [`ModifyProcessInstanceRequest`], which activates and terminates elements of a running
+/// process instance, e.g. to move a token that is stuck.
+///
+/// ```
+/// use zeebe_client::process_instance::{Activation, ModifyProcessInstance};
+///
+/// let request = ModifyProcessInstance::new(2251799813685300)
+///     .activate(Activation::element("retry-payment").variables(r#"{"attempt":2}"#))
+///     .terminate(2251799813685310)
+///     .build();
+/// assert_eq!(request.activate_instructions[0].ancestor_element_instance_key, -1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModifyProcessInstance {
+    request: ModifyProcessInstanceRequest,
+}
+
+impl ModifyProcessInstance {
+    pub fn new(process_instance_key: i64) -> ModifyProcessInstance {
+        ModifyProcessInstance {
+            request: ModifyProcessInstanceRequest {
+                process_instance_key,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn activate(mut self, activation: Activation) -> Self {
+        self.request.activate_instructions.push(activation.into());
+        self
+    }
+
+    /// Terminates the element instance with key `element_instance_key`, and its children.
+    pub fn terminate(mut self, element_instance_key: i64) -> Self {
+        self.request
+            .terminate_instructions
+            .push(TerminateInstruction {
+                element_instance_key,
+            });
+        self
+    }
+
+    pub fn build(self) -> ModifyProcessInstanceRequest {
+        self.request
+    }
+}
+
+impl From<ModifyProcessInstance> for ModifyProcessInstanceRequest {
+    fn from(builder: ModifyProcessInstance) -> Self {
+        builder.build()
+    }
+}
+
+/// Activates an element of a process instance, optionally creating variables with it.
+#[derive(Debug, Clone)]
+pub struct Activation {
+    instruction: ActivateInstruction,
+}
+
+impl Activation {
+    /// Activates the element with id `element_id` within the existing instance of its flow
+    /// scope, which must be unique.
+    pub fn element(element_id: impl Into<String>) -> Activation {
+        Activation {
+            instruction: ActivateInstruction {
+                element_id: element_id.into(),
+                ancestor_element_instance_key: -1,
+                variable_instructions: vec![],
+            },
+        }
+    }
+
+    /// Creates the element instance within the element instance with key
+    /// `ancestor_element_instance_key`, e.g. when its flow scope has several instances.
+    pub fn in_ancestor(mut self, ancestor_element_instance_key: i64) -> Self {
+        self.instruction.ancestor_element_instance_key = ancestor_element_instance_key;
+        self
+    }
+
+    /// Creates variables in the global scope of the process instance. `variables` must be a JSON
+    /// document with an object at the root.
+    pub fn variables(self, variables: impl Into<String>) -> Self {
+        self.scoped_variables("", variables)
+    }
+
+    /// Creates variables in the scope of the element with id `scope_id`, which is the activated
+    /// element or one of its flow scopes.
+    pub fn scoped_variables(
+        mut self,
+        scope_id: impl Into<String>,
+        variables: impl Into<String>,
+    ) -> Self {
+        self.instruction
+            .variable_instructions
+            .push(VariableInstruction {
+                variables: variables.into(),
+                scope_id: scope_id.into(),
+            });
+        self
+    }
+}
+
+impl From<Activation> for ActivateInstruction {
+    fn from(activation: Activation) -> Self {
+        activation.instruction
+    }
+}
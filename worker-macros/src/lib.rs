@@ -0,0 +1,279 @@
+mod variables;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, spanned::Spanned, DeriveInput, FnArg, Ident, ItemFn,
+    LitInt, LitStr, Type,
+};
+
+/// Implements `ZeebeVariables` from the struct's serde field names, taking `rename`,
+/// `rename_all`, `alias`, `default`, `flatten` and `skip` into account.
+///
+/// Fields that are neither `Option`s nor have a default are reported as required. Flattened
+/// fields must implement `ZeebeVariables` themselves.
+#[proc_macro_derive(ZeebeVariables, attributes(serde))]
+pub fn derive_zeebe_variables(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match variables::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Declares an async fn as the handler of a job type and generates a `<name>_worker` function
+/// that returns a [`WorkerBuilder`] for it.
+///
+/// ```ignore
+/// #[job_handler(type = "payment", timeout = "5m", max_jobs_active = 32)]
+/// async fn charge(input: Payment, #[headers] headers: Settings, job: Job) -> Result<Receipt, Error> {
+///     // ...
+/// }
+///
+/// let worker = charge_worker(client).build()?;
+/// ```
+///
+/// Arguments of type `Job` receive the job itself, arguments marked with `#[headers]` are
+/// deserialized from the custom headers, and the remaining argument (at most one) is deserialized
+/// from the job variables. Its type must implement `ZeebeVariables`, which names the variables
+/// that are fetched, and jobs missing any of its required variables fail with all of them listed.
+/// The return value is converted with `IntoJobOutcome`.
+///
+/// Supported options are `type` (defaults to the function name), `name`, `timeout`,
+/// `max_jobs_active`, `poll_interval` and `request_timeout`. Durations are given as strings like
+/// `"500ms"`, `"30s"`, `"5m"` or `"1h"`.
+///
+/// [`WorkerBuilder`]: https://docs.rs/zeebe-worker/latest/zeebe_worker/struct.WorkerBuilder.html
+#[proc_macro_attribute]
+pub fn job_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = HandlerOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(attr with parser);
+    let func = parse_macro_input!(item as ItemFn);
+
+    match expand(options, func) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct HandlerOptions {
+    job_type: Option<LitStr>,
+    name: Option<LitStr>,
+    timeout: Option<u64>,
+    max_jobs_active: Option<LitInt>,
+    poll_interval: Option<u64>,
+    request_timeout: Option<u64>,
+}
+
+impl HandlerOptions {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("type") {
+            self.job_type = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("timeout") {
+            self.timeout = Some(parse_duration(&meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("max_jobs_active") {
+            self.max_jobs_active = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("poll_interval") {
+            self.poll_interval = Some(parse_duration(&meta.value()?.parse()?)?);
+        } else if meta.path.is_ident("request_timeout") {
+            self.request_timeout = Some(parse_duration(&meta.value()?.parse()?)?);
+        } else {
+            return Err(meta.error("unsupported job_handler option"));
+        }
+        Ok(())
+    }
+}
+
+/// Parses durations like `500ms`, `30s`, `5m` or `1h` into milliseconds.
+fn parse_duration(lit: &LitStr) -> syn::Result<u64> {
+    let value = lit.value();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let factor = match unit.trim() {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "expected a unit of ms, s, m or h",
+            ))
+        }
+    };
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| syn::Error::new(lit.span(), "expected a duration like \"30s\""))?;
+    Ok(amount * factor)
+}
+
+enum Argument {
+    Job,
+    Headers(Type),
+    Variables(Type),
+}
+
+fn classify(arg: &mut FnArg) -> syn::Result<Argument> {
+    let arg = match arg {
+        FnArg::Typed(arg) => arg,
+        FnArg::Receiver(receiver) => {
+            return Err(syn::Error::new(
+                receiver.span(),
+                "job handlers cannot take self",
+            ))
+        }
+    };
+    let headers = arg.attrs.iter().any(|attr| attr.path().is_ident("headers"));
+    arg.attrs.retain(|attr| !attr.path().is_ident("headers"));
+    if headers {
+        return Ok(Argument::Headers((*arg.ty).clone()));
+    }
+    match &*arg.ty {
+        Type::Path(path) if path.path.segments.last().is_some_and(|s| s.ident == "Job") => {
+            Ok(Argument::Job)
+        }
+        ty => Ok(Argument::Variables(ty.clone())),
+    }
+}
+
+fn expand(options: HandlerOptions, mut func: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    if func.sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            func.sig.fn_token.span(),
+            "job handlers must be async",
+        ));
+    }
+    if !func.sig.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            func.sig.generics.span(),
+            "job handlers cannot be generic",
+        ));
+    }
+
+    let mut bindings = Vec::new();
+    let mut call_args = Vec::new();
+    let mut variables_type = None;
+    for (i, input) in func.sig.inputs.iter_mut().enumerate() {
+        let ident = Ident::new(&format!("__zeebe_arg{}", i), Span::call_site());
+        match classify(input)? {
+            Argument::Job => call_args.push(quote!(job.clone())),
+            Argument::Headers(ty) => {
+                bindings.push(quote! {
+                    let #ident: #ty = match job.custom_headers() {
+                        ::std::result::Result::Ok(headers) => headers,
+                        ::std::result::Result::Err(e) => {
+                            return ::zeebe_worker::JobOutcome::fail(::std::format!(
+                                "failed to deserialize custom headers: {}",
+                                e
+                            ))
+                        }
+                    };
+                });
+                call_args.push(quote!(#ident));
+            }
+            Argument::Variables(ty) => {
+                if variables_type.is_some() {
+                    return Err(syn::Error::new(
+                        ty.span(),
+                        "job handlers take at most one variables argument; mark custom headers with #[headers]",
+                    ));
+                }
+                bindings.push(quote! {
+                    let #ident: #ty = match job.checked_variables::<#ty>() {
+                        ::std::result::Result::Ok(variables) => variables,
+                        ::std::result::Result::Err(e) => {
+                            return ::zeebe_worker::JobOutcome::fail(::std::format!(
+                                "failed to deserialize job variables: {}",
+                                e
+                            ))
+                        }
+                    };
+                });
+                call_args.push(quote!(#ident));
+                variables_type = Some(ty);
+            }
+        }
+    }
+
+    let vis = &func.vis;
+    let fn_name = &func.sig.ident;
+    let worker_fn = format_ident!("{}_worker", fn_name);
+    let job_type = options
+        .job_type
+        .unwrap_or_else(|| LitStr::new(&fn_name.to_string(), fn_name.span()));
+    let name = options.name.map(|name| quote!(.name(#name)));
+    let timeout = options
+        .timeout
+        .map(|ms| quote!(.timeout(::std::time::Duration::from_millis(#ms))));
+    let max_jobs_active = options
+        .max_jobs_active
+        .map(|max| quote!(.max_jobs_active(#max)));
+    let poll_interval = options
+        .poll_interval
+        .map(|ms| quote!(.poll_interval(::std::time::Duration::from_millis(#ms))));
+    let request_timeout = options
+        .request_timeout
+        .map(|ms| quote!(.request_timeout(::std::time::Duration::from_millis(#ms))));
+    let fetch_variables = match &variables_type {
+        Some(ty) => quote!(<#ty as ::zeebe_worker::ZeebeVariables>::variable_names()),
+        None => quote!(::std::vec::Vec::new()),
+    };
+    let doc = format!(
+        "Builds a worker for jobs of type `{}` handled by [`{}`].",
+        job_type.value(),
+        fn_name
+    );
+
+    Ok(quote! {
+        #func
+
+        #[doc = #doc]
+        #vis fn #worker_fn(
+            client: ::zeebe_worker::zeebe_client::ZeebeClient,
+        ) -> ::zeebe_worker::WorkerBuilder {
+            ::zeebe_worker::WorkerBuilder::new(client, #job_type)
+                #name
+                #timeout
+                #max_jobs_active
+                #poll_interval
+                #request_timeout
+                .fetch_variables(#fetch_variables)
+                .handler(|job: ::zeebe_worker::Job| async move {
+                    #(#bindings)*
+                    ::zeebe_worker::IntoJobOutcome::into_job_outcome(#fn_name(#(#call_args),*).await)
+                })
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duration(value: &str) -> syn::Result<u64> {
+        parse_duration(&LitStr::new(value, Span::call_site()))
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(duration("500ms").unwrap(), 500);
+        assert_eq!(duration("30s").unwrap(), 30_000);
+        assert_eq!(duration("5m").unwrap(), 300_000);
+        assert_eq!(duration("1h").unwrap(), 3_600_000);
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(duration("30").is_err());
+        assert!(duration("30d").is_err());
+        assert!(duration("s").is_err());
+        assert!(duration("1.5s").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, parenthesized, Attribute, Data, DeriveInput, Fields, LitStr, Token, Type,
+};
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ZeebeVariables can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ZeebeVariables can only be derived for structs",
+            ))
+        }
+    };
+
+    let container = SerdeAttrs::parse(&input.attrs)?;
+    let mut names = Vec::new();
+    let mut required = Vec::new();
+    for field in fields {
+        let attrs = SerdeAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        if attrs.flatten {
+            let ty = &field.ty;
+            names.push(quote! {
+                names.extend(<#ty as ::zeebe_worker::ZeebeVariables>::variable_names());
+            });
+            required.push(quote! {
+                required.extend(<#ty as ::zeebe_worker::ZeebeVariables>::required_variables());
+            });
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("fields are named");
+        let name = match attrs.rename {
+            Some(rename) => rename,
+            None => {
+                let ident = ident.to_string();
+                let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+                match &container.rename_all {
+                    Some(rule) => apply_rename_rule(rule, ident)?,
+                    None => ident.to_owned(),
+                }
+            }
+        };
+        for name in std::iter::once(&name).chain(&attrs.aliases) {
+            names.push(quote!(names.push(::std::string::String::from(#name));));
+        }
+        // a field read from one of several names can't be checked by looking for a single key
+        let ambiguous = !attrs.aliases.is_empty();
+        if !attrs.default && !container.default && !ambiguous && !is_option(&field.ty) {
+            required.push(quote!(required.push(::std::string::String::from(#name));));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::zeebe_worker::ZeebeVariables for #ident #ty_generics #where_clause {
+            fn variable_names() -> ::std::vec::Vec<::std::string::String> {
+                let mut names = ::std::vec::Vec::new();
+                #(#names)*
+                names
+            }
+
+            fn required_variables() -> ::std::vec::Vec<::std::string::String> {
+                let mut required = ::std::vec::Vec::new();
+                #(#required)*
+                required
+            }
+        }
+    })
+}
+
+/// The subset of serde's attributes that affects which variables a struct is read from.
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<LitStr>,
+    aliases: Vec<String>,
+    default: bool,
+    flatten: bool,
+    skip: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<SerdeAttrs> {
+        let mut parsed = SerdeAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(rename) = deserialize_name(&meta)? {
+                        parsed.rename = Some(rename.value());
+                    }
+                } else if meta.path.is_ident("rename_all") {
+                    parsed.rename_all = deserialize_name(&meta)?;
+                } else if meta.path.is_ident("alias") {
+                    parsed
+                        .aliases
+                        .push(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    parsed.default = true;
+                    skip_value(&meta)?;
+                } else if meta.path.is_ident("flatten") {
+                    parsed.flatten = true;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    parsed.skip = true;
+                } else {
+                    skip_value(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+/// Reads `name = "..."` or the `deserialize` part of `name(serialize = "...", deserialize = "...")`.
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<LitStr>> {
+    if meta.input.peek(Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        if nested.path.is_ident("deserialize") {
+            name = Some(nested.value()?.parse()?);
+        } else {
+            skip_value(&nested)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in meta.input);
+        content.parse::<TokenStream>()?;
+    }
+    Ok(())
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// Mirrors serde's `rename_all` rules for snake_case field names.
+fn apply_rename_rule(rule: &LitStr, field: &str) -> syn::Result<String> {
+    let pascal: String = field
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    Ok(match rule.value().as_str() {
+        "lowercase" => field.to_ascii_lowercase(),
+        "UPPERCASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal,
+        "camelCase" => {
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        }
+        "snake_case" => field.to_owned(),
+        "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return Err(syn::Error::new(rule.span(), "unknown rename_all rule")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Span;
+
+    use super::*;
+
+    fn rename(rule: &str, field: &str) -> String {
+        apply_rename_rule(&LitStr::new(rule, Span::call_site()), field).unwrap()
+    }
+
+    #[test]
+    fn applies_rename_rules() {
+        assert_eq!(rename("lowercase", "order_id"), "order_id");
+        assert_eq!(rename("UPPERCASE", "order_id"), "ORDER_ID");
+        assert_eq!(rename("PascalCase", "order_id"), "OrderId");
+        assert_eq!(rename("camelCase", "order_id"), "orderId");
+        assert_eq!(rename("snake_case", "order_id"), "order_id");
+        assert_eq!(rename("SCREAMING_SNAKE_CASE", "order_id"), "ORDER_ID");
+        assert_eq!(rename("kebab-case", "order_id"), "order-id");
+        assert_eq!(rename("SCREAMING-KEBAB-CASE", "order_id"), "ORDER-ID");
+    }
+
+    #[test]
+    fn renames_single_words() {
+        assert_eq!(rename("camelCase", "amount"), "amount");
+        assert_eq!(rename("PascalCase", "amount"), "Amount");
+    }
+
+    #[test]
+    fn rejects_unknown_rules() {
+        assert!(apply_rename_rule(&LitStr::new("Title Case", Span::call_site()), "a").is_err());
+    }
+}
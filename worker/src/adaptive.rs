@@ -0,0 +1,259 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use metrics::Gauge;
+use tokio::sync::Semaphore;
+
+use crate::ReportError;
+
+/// Adjusts how many jobs a worker handles at once instead of using a fixed `max_jobs_active`.
+///
+/// The limit follows an AIMD scheme: it grows by one for each successful job while the worker
+/// uses at least half of it, and is multiplied by the backoff ratio whenever a handler is slower
+/// than the latency threshold or runs into its deadline, a handler reports a downstream error with
+/// [`JobOutcome::Overloaded`], or the gateway answers with `RESOURCE_EXHAUSTED`. Jobs otherwise
+/// failed by their handler count by their latency like any other, as business errors say nothing
+/// about load.
+///
+/// [`JobOutcome::Overloaded`]: crate::JobOutcome::Overloaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveConcurrency {
+    pub(crate) min: usize,
+    pub(crate) max: usize,
+    pub(crate) latency_threshold: Duration,
+    pub(crate) backoff_ratio: f64,
+}
+
+impl AdaptiveConcurrency {
+    pub fn new(min: usize, max: usize) -> AdaptiveConcurrency {
+        AdaptiveConcurrency {
+            min,
+            max,
+            latency_threshold: Duration::from_secs(10),
+            backoff_ratio: 0.9,
+        }
+    }
+
+    /// Handler latency above which the limit is decreased.
+    pub fn latency_threshold(mut self, latency_threshold: Duration) -> Self {
+        self.latency_threshold = latency_threshold;
+        self
+    }
+
+    /// Factor the limit is multiplied with when decreasing, between 0 and 1.
+    pub fn backoff_ratio(mut self, backoff_ratio: f64) -> Self {
+        self.backoff_ratio = backoff_ratio;
+        self
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.min >= 1
+            && self.min <= self.max
+            && self.backoff_ratio > 0.0
+            && self.backoff_ratio < 1.0
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Signal {
+    /// The job was handled and reported within this time.
+    Handled(Duration),
+    /// The handler ran into the job deadline or a downstream error, or the gateway pushed back.
+    Overloaded,
+}
+
+impl Signal {
+    /// The signal of a job whose outcome was reported, `overloaded` if its handler returned
+    /// [`JobOutcome::Overloaded`].
+    ///
+    /// [`JobOutcome::Overloaded`]: crate::JobOutcome::Overloaded
+    pub(crate) fn reported(
+        latency: Duration,
+        overloaded: bool,
+        result: &Result<(), ReportError>,
+    ) -> Signal {
+        match result {
+            _ if overloaded => Signal::Overloaded,
+            Err(ReportError::Rpc(status)) if status.code() == tonic::Code::ResourceExhausted => {
+                Signal::Overloaded
+            }
+            _ => Signal::Handled(latency),
+        }
+    }
+}
+
+struct State {
+    limit: usize,
+    // permits to remove from the semaphore once jobs in flight return them
+    debt: usize,
+}
+
+/// Owns a worker's job slots and resizes them according to [`AdaptiveConcurrency`].
+pub(crate) struct Limiter {
+    config: AdaptiveConcurrency,
+    slots: Arc<Semaphore>,
+    state: Mutex<State>,
+    gauge: Gauge,
+}
+
+impl Limiter {
+    pub(crate) fn new(config: AdaptiveConcurrency, initial: usize, gauge: Gauge) -> Limiter {
+        let limit = initial.clamp(config.min, config.max);
+        gauge.set(limit as f64);
+        Limiter {
+            config,
+            slots: Arc::new(Semaphore::new(limit)),
+            state: Mutex::new(State { limit, debt: 0 }),
+            gauge,
+        }
+    }
+
+    pub(crate) fn slots(&self) -> Arc<Semaphore> {
+        self.slots.clone()
+    }
+
+    pub(crate) fn sample(&self, signal: Signal) {
+        let mut state = self.state.lock().unwrap();
+        let forgotten = self.slots.forget_permits(state.debt);
+        state.debt -= forgotten;
+
+        let limit = match signal {
+            Signal::Handled(latency) if latency <= self.config.latency_threshold => {
+                let in_flight =
+                    (state.limit + state.debt).saturating_sub(self.slots.available_permits());
+                match in_flight * 2 >= state.limit {
+                    true => state.limit + 1,
+                    false => state.limit,
+                }
+            }
+            _ => (state.limit as f64 * self.config.backoff_ratio) as usize,
+        }
+        .clamp(self.config.min, self.config.max);
+
+        if limit > state.limit {
+            let increase = limit - state.limit;
+            let paid = increase.min(state.debt);
+            state.debt -= paid;
+            self.slots.add_permits(increase - paid);
+        } else if limit < state.limit {
+            let decrease = state.limit - limit;
+            state.debt += decrease - self.slots.forget_permits(decrease);
+        }
+        if limit != state.limit {
+            tracing::debug!(from = state.limit, to = limit, "adjusted concurrency limit");
+            state.limit = limit;
+            self.gauge.set(limit as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAST: Signal = Signal::Handled(Duration::from_millis(10));
+
+    fn limiter(min: usize, max: usize, initial: usize) -> Limiter {
+        let config = AdaptiveConcurrency::new(min, max)
+            .latency_threshold(Duration::from_secs(1))
+            .backoff_ratio(0.5);
+        Limiter::new(config, initial, Gauge::noop())
+    }
+
+    fn state(limiter: &Limiter) -> (usize, usize, usize) {
+        let state = limiter.state.lock().unwrap();
+        (state.limit, state.debt, limiter.slots.available_permits())
+    }
+
+    #[test]
+    fn starts_within_bounds() {
+        assert_eq!(state(&limiter(2, 8, 32)), (8, 0, 8));
+        assert_eq!(state(&limiter(2, 8, 1)), (2, 0, 2));
+    }
+
+    #[test]
+    fn grows_while_at_least_half_is_used() {
+        let limiter = limiter(1, 10, 4);
+        let _in_flight = limiter.slots.clone().try_acquire_many_owned(2).unwrap();
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter), (5, 0, 3));
+    }
+
+    #[test]
+    fn keeps_the_limit_while_mostly_idle() {
+        let limiter = limiter(1, 10, 4);
+        let _in_flight = limiter.slots.clone().try_acquire_owned().unwrap();
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter), (4, 0, 3));
+    }
+
+    #[test]
+    fn shrinks_on_slow_handlers_and_overload() {
+        let limiter = limiter(1, 10, 8);
+        limiter.sample(Signal::Handled(Duration::from_secs(2)));
+        assert_eq!(state(&limiter), (4, 0, 4));
+        limiter.sample(Signal::Overloaded);
+        assert_eq!(state(&limiter), (2, 0, 2));
+    }
+
+    #[test]
+    fn takes_downstream_and_gateway_overload_as_signals() {
+        let latency = Duration::from_millis(10);
+        assert_eq!(Signal::reported(latency, false, &Ok(())), FAST);
+        assert_eq!(Signal::reported(latency, true, &Ok(())), Signal::Overloaded);
+        let exhausted = Err(ReportError::Rpc(tonic::Status::resource_exhausted("busy")));
+        assert_eq!(
+            Signal::reported(latency, false, &exhausted),
+            Signal::Overloaded
+        );
+        let not_active = Err(ReportError::JobNotActive(1));
+        assert_eq!(Signal::reported(latency, false, &not_active), FAST);
+    }
+
+    #[test]
+    fn shrinks_on_downstream_overload() {
+        let limiter = limiter(1, 10, 8);
+        limiter.sample(Signal::reported(Duration::ZERO, true, &Ok(())));
+        assert_eq!(state(&limiter), (4, 0, 4));
+    }
+
+    #[test]
+    fn stays_within_bounds() {
+        let limiter = limiter(2, 3, 3);
+        let in_flight = limiter.slots.clone().try_acquire_many_owned(3).unwrap();
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter).0, 3);
+        drop(in_flight);
+        limiter.sample(Signal::Overloaded);
+        limiter.sample(Signal::Overloaded);
+        assert_eq!(state(&limiter), (2, 0, 2));
+    }
+
+    #[test]
+    fn removes_permits_of_jobs_in_flight_once_returned() {
+        let limiter = limiter(1, 10, 4);
+        let in_flight = limiter.slots.clone().try_acquire_many_owned(4).unwrap();
+        limiter.sample(Signal::Overloaded);
+        assert_eq!(state(&limiter), (2, 2, 0));
+
+        drop(in_flight);
+        assert_eq!(limiter.slots.available_permits(), 4);
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter), (2, 0, 2));
+    }
+
+    #[test]
+    fn pays_off_debt_before_adding_permits() {
+        let limiter = limiter(1, 10, 4);
+        let in_flight = limiter.slots.clone().try_acquire_many_owned(4).unwrap();
+        limiter.sample(Signal::Overloaded);
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter), (3, 1, 0));
+
+        drop(in_flight);
+        limiter.sample(FAST);
+        assert_eq!(state(&limiter), (3, 0, 3));
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use tokio::sync::oneshot;
+
+use crate::{BoxFuture, IntoJobOutcome, Job, JobHandler, JobOutcome};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// A fixed number of threads dedicated to blocking job handlers, see
+/// [`WorkerBuilder::blocking_handler_on`].
+///
+/// The threads exit once the pool and all workers using it are dropped.
+///
+/// [`WorkerBuilder::blocking_handler_on`]: crate::WorkerBuilder::blocking_handler_on
+#[derive(Clone)]
+pub struct ThreadPool {
+    tasks: mpsc::Sender<Task>,
+}
+
+impl ThreadPool {
+    pub fn new(name: &str, size: usize) -> std::io::Result<ThreadPool> {
+        if size == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "thread pool needs at least one thread",
+            ));
+        }
+        let (tasks, queue) = mpsc::channel::<Task>();
+        let queue = Arc::new(Mutex::new(queue));
+        for i in 0..size {
+            let queue = queue.clone();
+            thread::Builder::new()
+                .name(format!("{}-{}", name, i))
+                .spawn(move || loop {
+                    let task = queue.lock().unwrap().recv();
+                    match task {
+                        Ok(task) => task(),
+                        Err(_) => break,
+                    }
+                })?;
+        }
+        Ok(ThreadPool { tasks })
+    }
+
+    async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> R + Send + 'static,
+    ) -> thread::Result<R> {
+        let (result, received) = oneshot::channel();
+        self.tasks
+            .send(Box::new(move || {
+                let _ = result.send(panic::catch_unwind(AssertUnwindSafe(f)));
+            }))
+            .expect("pool threads only exit once the pool is dropped");
+        received.await.expect("pool threads always send a result")
+    }
+}
+
+/// Runs a synchronous handler outside of the async runtime, either on Tokio's blocking threads or
+/// on a [`ThreadPool`].
+///
+/// Handlers run to completion even if the worker is configured to abort handlers at the job
+/// deadline; they can check [`Job::is_cancelled`] to stop early.
+pub struct BlockingHandler<F> {
+    handler: Arc<F>,
+    pool: Option<ThreadPool>,
+}
+
+impl<F> BlockingHandler<F> {
+    pub fn new(handler: F, pool: Option<ThreadPool>) -> BlockingHandler<F> {
+        BlockingHandler {
+            handler: Arc::new(handler),
+            pool,
+        }
+    }
+}
+
+impl<F, R> JobHandler for BlockingHandler<F>
+where
+    F: Fn(Job) -> R + Send + Sync + 'static,
+    R: IntoJobOutcome + Send + 'static,
+{
+    fn call(&self, job: Job) -> BoxFuture<JobOutcome> {
+        let handler = self.handler.clone();
+        let pool = self.pool.clone();
+        Box::pin(async move {
+            let result = match pool {
+                Some(pool) => pool.run(move || handler(job)).await,
+                None => match tokio::task::spawn_blocking(move || handler(job)).await {
+                    Ok(result) => Ok(result),
+                    Err(e) if e.is_panic() => Err(e.into_panic()),
+                    Err(e) => return JobOutcome::fail(e.to_string()),
+                },
+            };
+            match result {
+                Ok(result) => result.into_job_outcome(),
+                // behave like a panicking async handler
+                Err(panic) => panic::resume_unwind(panic),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use tokio::task::JoinSet;
+
+    use super::*;
+    use crate::testing::TestJob;
+
+    #[tokio::test]
+    async fn runs_at_most_size_jobs_at_once() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(BlockingHandler::new(
+            {
+                let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+                move |_: Job| {
+                    let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_in_flight.fetch_max(running, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                }
+            },
+            Some(ThreadPool::new("test", 2).unwrap()),
+        ));
+        let mut jobs = JoinSet::new();
+        for key in 0..6 {
+            let handler = handler.clone();
+            jobs.spawn(async move { handler.call(TestJob::new("report").key(key).job()).await });
+        }
+        while let Some(outcome) = jobs.join_next().await {
+            assert_eq!(outcome.unwrap(), JobOutcome::complete());
+        }
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    async fn panics(handler: Arc<impl JobHandler>) -> bool {
+        let called = tokio::spawn(async move { handler.call(TestJob::new("report").job()).await });
+        tokio::time::timeout(Duration::from_secs(5), called)
+            .await
+            .expect("handler doesn't hang")
+            .is_err_and(|err| err.is_panic())
+    }
+
+    #[tokio::test]
+    async fn propagates_panics_and_keeps_the_pool_running() {
+        let pool = ThreadPool::new("test", 1).unwrap();
+        let panicking = BlockingHandler::new(
+            |job: Job| {
+                if job.key == 1 {
+                    panic!("report failed");
+                }
+            },
+            Some(pool.clone()),
+        );
+        let panicking = Arc::new(panicking);
+        assert!(panics(panicking.clone()).await);
+        assert!(panics(panicking).await);
+
+        let handler = BlockingHandler::new(|_: Job| (), Some(pool));
+        let outcome = tokio::time::timeout(
+            Duration::from_secs(5),
+            handler.call(TestJob::new("report").key(2).job()),
+        )
+        .await
+        .expect("pool is still running");
+        assert_eq!(outcome, JobOutcome::complete());
+    }
+
+    #[tokio::test]
+    async fn propagates_panics_on_tokio_threads() {
+        let handler =
+            BlockingHandler::new(|_: Job| -> JobOutcome { panic!("report failed") }, None);
+        assert!(panics(Arc::new(handler)).await);
+    }
+
+    #[test]
+    fn needs_a_thread() {
+        assert!(ThreadPool::new("test", 0).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{sync::watch, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+/// Controls what happens when a job approaches the deadline after which the broker hands it to
+/// another worker.
+#[derive(Debug, Clone)]
+pub struct DeadlinePolicy {
+    /// How long before the job deadline the job's cancellation token fires.
+    pub margin: Duration,
+    /// Whether the handler future is dropped once the cancellation token fires. Otherwise the
+    /// handler is expected to observe the token itself.
+    pub abort_handler: bool,
+}
+
+impl Default for DeadlinePolicy {
+    fn default() -> Self {
+        DeadlinePolicy {
+            margin: Duration::from_secs(1),
+            abort_handler: false,
+        }
+    }
+}
+
+/// Time left until `deadline` (a UNIX epoch timestamp in milliseconds) minus `margin`, or `None`
+/// if that point has already passed.
+pub(crate) fn remaining(deadline: i64, margin: Duration) -> Option<Duration> {
+    let deadline = UNIX_EPOCH + Duration::from_millis(deadline.max(0) as u64);
+    deadline
+        .checked_sub(margin)?
+        .duration_since(SystemTime::now())
+        .ok()
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Cancels a token once a job's deadline minus the safety margin is reached.
+///
+/// The deadline can be extended when the job is activated again while it is being handled. The
+/// token stays cancelled once it fired, but [`Watchdog::expired`] follows the latest deadline.
+pub(crate) struct Watchdog {
+    token: CancellationToken,
+    margin: Duration,
+    deadline: Arc<watch::Sender<i64>>,
+    timer: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(deadline: i64, margin: Duration) -> Watchdog {
+        let token = CancellationToken::new();
+        let initial = remaining(deadline, margin);
+        let (deadline, mut changed) = watch::channel(deadline);
+        let timer = match initial {
+            Some(_) => {
+                let token = token.clone();
+                Some(tokio::spawn(async move {
+                    // re-armed whenever the deadline is extended
+                    loop {
+                        let deadline = *changed.borrow_and_update();
+                        let Some(remaining) = remaining(deadline, margin) else {
+                            break;
+                        };
+                        tokio::select! {
+                            _ = tokio::time::sleep(remaining) => break,
+                            _ = changed.changed() => {}
+                        }
+                    }
+                    token.cancel();
+                }))
+            }
+            None => {
+                token.cancel();
+                None
+            }
+        };
+        Watchdog {
+            token,
+            margin,
+            deadline: Arc::new(deadline),
+            timer,
+        }
+    }
+
+    pub(crate) fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub(crate) fn expired(&self) -> bool {
+        remaining(*self.deadline.borrow(), self.margin).is_none()
+    }
+
+    pub(crate) fn extender(&self) -> Extender {
+        Extender {
+            deadline: self.deadline.clone(),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+    }
+}
+
+/// Moves the deadline of a running [`Watchdog`].
+#[derive(Clone)]
+pub(crate) struct Extender {
+    deadline: Arc<watch::Sender<i64>>,
+}
+
+impl Extender {
+    /// Moves the deadline to `deadline` if that is later.
+    pub(crate) fn extend(&self, deadline: i64) {
+        self.deadline.send_if_modified(|current| {
+            let later = deadline > *current;
+            if later {
+                *current = deadline;
+            }
+            later
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARGIN: Duration = Duration::from_secs(1);
+
+    fn in_ms(ms: i64) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+            + ms
+    }
+
+    #[test]
+    fn leaves_the_margin_before_the_deadline() {
+        let left = remaining(in_ms(10_000), MARGIN).unwrap();
+        assert!(left <= Duration::from_secs(9) && left > Duration::from_millis(8_900));
+        assert_eq!(remaining(in_ms(500), MARGIN), None);
+        assert_eq!(remaining(in_ms(-1_000), Duration::ZERO), None);
+        assert_eq!(remaining(0, Duration::ZERO), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancels_the_token_at_the_deadline() {
+        let watchdog = Watchdog::start(in_ms(10_000), MARGIN);
+        let token = watchdog.token();
+        tokio::time::sleep(Duration::from_millis(8_800)).await;
+        assert!(!token.is_cancelled());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn extends_the_deadline() {
+        let watchdog = Watchdog::start(in_ms(10_000), MARGIN);
+        let token = watchdog.token();
+        watchdog.extender().extend(in_ms(20_000));
+        // earlier deadlines don't shorten it again
+        watchdog.extender().extend(in_ms(5_000));
+        tokio::time::sleep(Duration::from_millis(9_100)).await;
+        assert!(!token.is_cancelled());
+        tokio::time::sleep(Duration::from_millis(10_000)).await;
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn cancels_the_token_of_expired_jobs_at_once() {
+        let watchdog = Watchdog::start(in_ms(500), MARGIN);
+        assert!(watchdog.token().is_cancelled());
+        assert!(watchdog.expired());
+        watchdog.extender().extend(in_ms(60_000));
+        assert!(!watchdog.expired());
+    }
+}
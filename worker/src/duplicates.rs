@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::deadline::Extender;
+
+/// What a worker does with a job that is activated again while its handler is still running,
+/// e.g. because the handler took longer than the job timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateJobs {
+    /// Drops the new activation. The running handler's outcome is discarded if the original
+    /// deadline has passed, and the job is activated again after the new timeout.
+    Skip,
+    /// Drops the new activation but moves the running handler's deadline to the new one, so that
+    /// its outcome is reported for both.
+    #[default]
+    Merge,
+}
+
+pub(crate) enum Registration {
+    Registered(InFlightGuard),
+    Duplicate,
+}
+
+/// Keys of the jobs a worker is currently handling.
+#[derive(Clone, Default)]
+pub(crate) struct InFlightJobs {
+    jobs: Arc<Mutex<HashMap<i64, Extender>>>,
+}
+
+impl InFlightJobs {
+    /// Registers a job unless it is already being handled. A running handler is merged with the
+    /// new activation according to `policy`.
+    pub(crate) fn register(
+        &self,
+        key: i64,
+        deadline: i64,
+        extender: Extender,
+        policy: DuplicateJobs,
+    ) -> Registration {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(running) = jobs.get(&key) {
+            if policy == DuplicateJobs::Merge {
+                running.extend(deadline);
+            }
+            return Registration::Duplicate;
+        }
+        jobs.insert(key, extender);
+        Registration::Registered(InFlightGuard {
+            jobs: self.jobs.clone(),
+            key,
+        })
+    }
+}
+
+/// Removes a job from [`InFlightJobs`] once it has been handled.
+pub(crate) struct InFlightGuard {
+    jobs: Arc<Mutex<HashMap<i64, Extender>>>,
+    key: i64,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.jobs.lock().unwrap().remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::deadline::Watchdog;
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64
+    }
+
+    // watchdogs of jobs past their deadline have no timer, so these tests need no runtime
+
+    #[test]
+    fn skips_duplicates() {
+        let jobs = InFlightJobs::default();
+        let watchdog = Watchdog::start(now() - 1000, Duration::ZERO);
+        let _guard = jobs.register(1, now(), watchdog.extender(), DuplicateJobs::Skip);
+        let duplicate = jobs.register(1, now() + 60_000, watchdog.extender(), DuplicateJobs::Skip);
+        assert!(matches!(duplicate, Registration::Duplicate));
+        assert!(watchdog.expired());
+    }
+
+    #[test]
+    fn merges_duplicates_into_the_running_handler() {
+        let jobs = InFlightJobs::default();
+        let watchdog = Watchdog::start(now() - 1000, Duration::ZERO);
+        let _guard = jobs.register(1, now(), watchdog.extender(), DuplicateJobs::Merge);
+        let duplicate = jobs.register(1, now() + 60_000, watchdog.extender(), DuplicateJobs::Merge);
+        assert!(matches!(duplicate, Registration::Duplicate));
+        assert!(!watchdog.expired());
+    }
+
+    #[test]
+    fn releases_jobs_once_handled() {
+        let jobs = InFlightJobs::default();
+        let watchdog = Watchdog::start(now() - 1000, Duration::ZERO);
+        let guard = jobs.register(1, now(), watchdog.extender(), DuplicateJobs::Skip);
+        assert!(matches!(
+            jobs.register(2, now(), watchdog.extender(), DuplicateJobs::Skip),
+            Registration::Registered(_)
+        ));
+        drop(guard);
+        assert!(matches!(
+            jobs.register(1, now(), watchdog.extender(), DuplicateJobs::Skip),
+            Registration::Registered(_)
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+use std::{fmt::Display, future::Future, pin::Pin, sync::Mutex};
+
+use tower::{Service, ServiceExt};
+
+use crate::{IntoJobOutcome, Job, JobOutcome};
+
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Handles the jobs activated by a worker.
+///
+/// Implemented for every `Fn(Job) -> impl Future<Output = JobOutcome>`.
+pub trait JobHandler: Send + Sync + 'static {
+    fn call(&self, job: Job) -> BoxFuture<JobOutcome>;
+}
+
+impl<F, Fut> JobHandler for F
+where
+    F: Fn(Job) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = JobOutcome> + Send + 'static,
+{
+    fn call(&self, job: Job) -> BoxFuture<JobOutcome> {
+        Box::pin(self(job))
+    }
+}
+
+/// Adapts a [`tower::Service`] to a [`JobHandler`], see [`WorkerBuilder::service`].
+///
+/// The service is cloned for every job, so layers that keep state across calls need to share it
+/// between clones (as `ConcurrencyLimit` does, for example).
+///
+/// [`WorkerBuilder::service`]: crate::WorkerBuilder::service
+pub struct ServiceHandler<S> {
+    service: Mutex<S>,
+}
+
+impl<S> ServiceHandler<S> {
+    pub fn new(service: S) -> ServiceHandler<S> {
+        ServiceHandler {
+            service: Mutex::new(service),
+        }
+    }
+}
+
+impl<S> JobHandler for ServiceHandler<S>
+where
+    S: Service<Job> + Clone + Send + 'static,
+    S::Response: IntoJobOutcome,
+    S::Error: Display,
+    S::Future: Send + 'static,
+{
+    fn call(&self, job: Job) -> BoxFuture<JobOutcome> {
+        let service = self.service.lock().unwrap().clone();
+        Box::pin(async move {
+            match service.oneshot(job).await {
+                Ok(response) => response.into_job_outcome(),
+                Err(e) => JobOutcome::fail(e.to_string()),
+            }
+        })
+    }
+}
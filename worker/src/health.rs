@@ -0,0 +1,337 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+// how often a worker loop reports that it's alive while it waits, e.g. for capacity
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Result of the most recent activate jobs request of a job type.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollStatus {
+    /// UNIX epoch timestamp in milliseconds
+    pub polled_at: u64,
+    pub ok: bool,
+    pub jobs: usize,
+    pub error: Option<String>,
+    /// UNIX epoch timestamp in milliseconds of the most recent successful poll
+    pub last_success_at: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct JobTypeHealth {
+    poll: Option<PollStatus>,
+    /// UNIX epoch timestamp in milliseconds
+    heartbeat_at: u64,
+    paused: bool,
+}
+
+/// Collects the poll results of workers, see [`WorkerBuilder::health`].
+///
+/// [`WorkerBuilder::health`]: crate::WorkerBuilder::health
+#[derive(Debug, Clone, Default)]
+pub struct Health {
+    job_types: Arc<Mutex<BTreeMap<String, JobTypeHealth>>>,
+}
+
+impl Health {
+    pub fn new() -> Health {
+        Health::default()
+    }
+
+    pub(crate) fn record(&self, job_type: &str, result: Result<usize, &tonic::Status>) {
+        let now = epoch_millis(SystemTime::now());
+        let mut job_types = self.job_types.lock().unwrap();
+        let health = job_types.entry(job_type.to_owned()).or_default();
+        let last_success_at = match result {
+            Ok(_) => Some(now),
+            Err(_) => health.poll.as_ref().and_then(|poll| poll.last_success_at),
+        };
+        health.heartbeat_at = now;
+        health.poll = Some(PollStatus {
+            polled_at: now,
+            ok: result.is_ok(),
+            jobs: *result.as_ref().unwrap_or(&0),
+            error: result.as_ref().err().map(|status| status.to_string()),
+            last_success_at,
+        });
+    }
+
+    /// Records that the worker loop of `job_type` is running, whether it polls or waits.
+    pub(crate) fn beat(&self, job_type: &str) {
+        let now = epoch_millis(SystemTime::now());
+        let mut job_types = self.job_types.lock().unwrap();
+        job_types
+            .entry(job_type.to_owned())
+            .or_default()
+            .heartbeat_at = now;
+    }
+
+    /// Marks a running job type as paused or resumed; stopped job types stay forgotten.
+    pub(crate) fn set_paused(&self, job_type: &str, paused: bool) {
+        if let Some(health) = self.job_types.lock().unwrap().get_mut(job_type) {
+            health.paused = paused;
+        }
+    }
+
+    /// Forgets a job type whose worker has stopped.
+    pub(crate) fn remove(&self, job_type: &str) {
+        self.job_types.lock().unwrap().remove(job_type);
+    }
+
+    /// Last poll result per job type.
+    pub fn polls(&self) -> BTreeMap<String, PollStatus> {
+        self.job_types
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(job_type, health)| Some((job_type.clone(), health.poll.clone()?)))
+            .collect()
+    }
+
+    /// Whether the worker loop of every running job type has reported within `window`. Paused
+    /// job types are not taken into account, and the loops also report while they wait for
+    /// capacity, so busy workers stay live.
+    pub fn is_live(&self, window: Duration) -> bool {
+        let since = epoch_millis(SystemTime::now() - window);
+        self.job_types
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|health| !health.paused)
+            .all(|health| health.heartbeat_at >= since)
+    }
+
+    /// Whether any job type has been polled successfully within `window`.
+    pub fn is_ready(&self, window: Duration) -> bool {
+        let since = epoch_millis(SystemTime::now() - window);
+        self.job_types.lock().unwrap().values().any(|health| {
+            health
+                .poll
+                .as_ref()
+                .and_then(|poll| poll.last_success_at)
+                .is_some_and(|at| at >= since)
+        })
+    }
+}
+
+/// Reports the worker loop of a job type as alive while it runs.
+pub(crate) struct Heartbeat {
+    health: Option<Health>,
+    job_type: String,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(health: Option<Health>, job_type: &str) -> Heartbeat {
+        Heartbeat {
+            health,
+            job_type: job_type.to_owned(),
+        }
+    }
+
+    /// Runs `future`, beating every second until it completes. The job type is forgotten
+    /// afterwards, since its loop is done.
+    pub(crate) async fn run<F: Future>(&self, future: F) -> F::Output {
+        let health = match &self.health {
+            Some(health) => health,
+            None => return future.await,
+        };
+        tokio::pin!(future);
+        let mut ticks = tokio::time::interval(HEARTBEAT_INTERVAL);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let output = loop {
+            tokio::select! {
+                output = &mut future => break output,
+                _ = ticks.tick() => health.beat(&self.job_type),
+            }
+        };
+        health.remove(&self.job_type);
+        output
+    }
+}
+
+fn epoch_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(feature = "health")]
+pub use server::HealthServer;
+
+#[cfg(feature = "health")]
+mod server {
+    use std::{convert::Infallible, net::SocketAddr, time::Duration};
+
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Method, Request, Response, StatusCode,
+    };
+    use tokio_util::sync::CancellationToken;
+    use zeebe_client::{api::TopologyRequest, ZeebeClient};
+
+    use super::Health;
+
+    /// Serves `/health/live` and `/health/ready` for Kubernetes probes.
+    ///
+    /// Both respond with the last poll result per job type as JSON. Liveness requires the worker
+    /// loop of every running job type to have reported within the window, which it does while
+    /// polling or waiting for capacity; paused and stopped job types are left out. Readiness
+    /// requires a successful poll within the window or, if a client is configured with
+    /// [`HealthServer::topology_probe`], a successful topology request.
+    #[derive(Clone)]
+    pub struct HealthServer {
+        health: Health,
+        window: Duration,
+        client: Option<ZeebeClient>,
+    }
+
+    impl HealthServer {
+        pub fn new(health: Health) -> HealthServer {
+            HealthServer {
+                health,
+                window: Duration::from_secs(60),
+                client: None,
+            }
+        }
+
+        pub fn window(mut self, window: Duration) -> Self {
+            self.window = window;
+            self
+        }
+
+        pub fn topology_probe(mut self, client: ZeebeClient) -> Self {
+            self.client = Some(client);
+            self
+        }
+
+        /// Serves requests on `addr` until `shutdown` is cancelled.
+        pub async fn serve(
+            self,
+            addr: SocketAddr,
+            shutdown: CancellationToken,
+        ) -> Result<(), hyper::Error> {
+            let make_service = make_service_fn(move |_| {
+                let server = self.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        let server = server.clone();
+                        async move { Ok::<_, Infallible>(server.respond(request).await) }
+                    }))
+                }
+            });
+            tracing::info!(%addr, "serving worker health");
+            hyper::Server::try_bind(&addr)?
+                .serve(make_service)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+        }
+
+        async fn respond(&self, request: Request<Body>) -> Response<Body> {
+            let healthy = match (request.method(), request.uri().path()) {
+                (&Method::GET, "/health/live") => self.health.is_live(self.window),
+                (&Method::GET, "/health/ready") => {
+                    self.health.is_ready(self.window) || self.probe_topology().await
+                }
+                _ => {
+                    return Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::empty())
+                        .expect("response is valid")
+                }
+            };
+            let body = serde_json::to_string(&self.health.polls())
+                .expect("poll results can be serialized");
+            Response::builder()
+                .status(match healthy {
+                    true => StatusCode::OK,
+                    false => StatusCode::SERVICE_UNAVAILABLE,
+                })
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .expect("response is valid")
+        }
+
+        async fn probe_topology(&self) -> bool {
+            let mut client = match &self.client {
+                Some(client) => client.clone(),
+                None => return false,
+            };
+            match client.topology(TopologyRequest {}).await {
+                Ok(_) => true,
+                Err(status) => {
+                    tracing::debug!(error = ?status, "topology probe failed");
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn stale(health: &Health, job_type: &str) {
+        health
+            .job_types
+            .lock()
+            .unwrap()
+            .get_mut(job_type)
+            .unwrap()
+            .heartbeat_at = 0;
+    }
+
+    #[test]
+    fn waiting_workers_stay_live() {
+        let health = Health::new();
+        health.record("payment", Ok(1));
+        stale(&health, "payment");
+        assert!(!health.is_live(WINDOW));
+
+        // e.g. while all slots are busy
+        health.beat("payment");
+        assert!(health.is_live(WINDOW));
+    }
+
+    #[test]
+    fn paused_job_types_are_left_out() {
+        let health = Health::new();
+        health.beat("payment");
+        health.set_paused("payment", true);
+        stale(&health, "payment");
+        assert!(health.is_live(WINDOW));
+
+        health.set_paused("payment", false);
+        assert!(!health.is_live(WINDOW));
+    }
+
+    #[test]
+    fn stopped_job_types_are_left_out() {
+        let health = Health::new();
+        health.beat("payment");
+        stale(&health, "payment");
+        health.remove("payment");
+        health.set_paused("payment", false);
+        assert!(health.is_live(WINDOW));
+        assert!(health.polls().is_empty());
+    }
+
+    #[test]
+    fn failed_polls_keep_the_last_success() {
+        let health = Health::new();
+        health.record("payment", Ok(3));
+        health.record("payment", Err(&tonic::Status::unavailable("gateway down")));
+        let poll = &health.polls()["payment"];
+        assert!(!poll.ok);
+        assert!(poll.last_success_at.is_some());
+        assert!(health.is_ready(WINDOW));
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::BoxFuture;
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("invalid idempotency record: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Records which business operations have completed, and with which result.
+///
+/// Keys identify an operation, e.g. `charge:<order id>`. Results are JSON documents.
+pub trait IdempotencyStore: Send + Sync + 'static {
+    /// The result recorded for `key`, if the operation has completed.
+    fn get(&self, key: String) -> BoxFuture<Result<Option<String>, StoreError>>;
+
+    /// Records that the operation `key` completed with `result`.
+    fn put(&self, key: String, result: String) -> BoxFuture<Result<(), StoreError>>;
+}
+
+/// Keeps records in memory, so they only survive as long as the process.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    records: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl IdempotencyStore for MemoryStore {
+    fn get(&self, key: String) -> BoxFuture<Result<Option<String>, StoreError>> {
+        let result = self.records.lock().unwrap().get(&key).cloned();
+        Box::pin(async move { Ok(result) })
+    }
+
+    fn put(&self, key: String, result: String) -> BoxFuture<Result<(), StoreError>> {
+        self.records.lock().unwrap().insert(key, result);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    key: String,
+    result: String,
+}
+
+/// Appends records to a file with one JSON document per line, and keeps them in memory for
+/// lookups.
+#[derive(Clone)]
+pub struct FileStore {
+    records: MemoryStore,
+    file: Arc<Mutex<File>>,
+}
+
+impl FileStore {
+    /// Opens or creates the file at `path` and loads the records in it.
+    pub fn open(path: impl AsRef<Path>) -> Result<FileStore, StoreError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        let mut records = HashMap::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record = serde_json::from_str(&line)?;
+            records.insert(record.key, record.result);
+        }
+        Ok(FileStore {
+            records: MemoryStore {
+                records: Arc::new(Mutex::new(records)),
+            },
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+}
+
+impl IdempotencyStore for FileStore {
+    fn get(&self, key: String) -> BoxFuture<Result<Option<String>, StoreError>> {
+        self.records.get(key)
+    }
+
+    fn put(&self, key: String, result: String) -> BoxFuture<Result<(), StoreError>> {
+        let store = self.clone();
+        Box::pin(async move {
+            let mut line = serde_json::to_string(&Record {
+                key: key.clone(),
+                result: result.clone(),
+            })?;
+            line.push('\n');
+            let file = store.file.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut file = file.lock().unwrap();
+                file.write_all(line.as_bytes())?;
+                file.sync_data()
+            })
+            .await
+            .expect("writing a record doesn't panic")?;
+            store.records.put(key, result).await
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum IdempotencyError<E> {
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error("{0}")]
+    Operation(E),
+}
+
+/// Runs business operations at most once per key, as far as the [`IdempotencyStore`] can tell.
+///
+/// Handlers use this for side effects that must not be repeated when a job is retried or
+/// activated again, e.g. after the worker crashed between charging a card and completing the job.
+/// Concurrent runs of the same key in one worker are prevented by [`DuplicateJobs`]; across
+/// processes, the store must be shared and the operation itself should tolerate a rare repeat.
+///
+/// ```no_run
+/// # use serde::{Deserialize, Serialize};
+/// # use zeebe_worker::{FileStore, Idempotency, IntoJobOutcome, Job, JobOutcome, WorkerBuilder};
+/// # #[derive(Deserialize)]
+/// # struct Order {
+/// #     id: String,
+/// # }
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Receipt {}
+/// # async fn charge(_: &Order) -> Result<Receipt, String> { Ok(Receipt {}) }
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let client = zeebe_worker::testing::client();
+/// let idempotency = Idempotency::new(FileStore::open("payments.jsonl")?);
+/// let worker = WorkerBuilder::new(client, "payment")
+///     .handler(move |job: Job| {
+///         let idempotency = idempotency.clone();
+///         async move {
+///             let order: Order = match job.variables() {
+///                 Ok(order) => order,
+///                 Err(err) => return JobOutcome::fail(err.to_string()),
+///             };
+///             idempotency
+///                 .run(format!("charge:{}", order.id), || charge(&order))
+///                 .await
+///                 .into_job_outcome()
+///         }
+///     })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`DuplicateJobs`]: crate::DuplicateJobs
+#[derive(Clone)]
+pub struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+}
+
+impl Idempotency {
+    pub fn new(store: impl IdempotencyStore) -> Idempotency {
+        Idempotency {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Returns the recorded result of `key`, or runs `operation` and records its result if it
+    /// succeeds. Failed operations are not recorded, so they run again on the next attempt.
+    pub async fn run<T, E, F, Fut>(
+        &self,
+        key: impl Into<String>,
+        operation: F,
+    ) -> Result<T, IdempotencyError<E>>
+    where
+        T: Serialize + DeserializeOwned,
+        E: Display,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let key = key.into();
+        if let Some(result) = self.store.get(key.clone()).await? {
+            tracing::debug!(
+                key,
+                "operation already completed, returning recorded result"
+            );
+            return Ok(serde_json::from_str(&result).map_err(StoreError::from)?);
+        }
+        let result = operation().await.map_err(IdempotencyError::Operation)?;
+        let recorded = serde_json::to_string(&result).map_err(StoreError::from)?;
+        self.store.put(key, recorded).await?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("idempotency-{}-{}.jsonl", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn runs_operations_once() {
+        let idempotency = Idempotency::new(MemoryStore::new());
+        let runs = AtomicUsize::new(0);
+        let charge = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>(42)
+        };
+        assert_eq!(idempotency.run("charge:1", charge).await.unwrap(), 42);
+        assert_eq!(idempotency.run("charge:1", charge).await.unwrap(), 42);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(idempotency.run("charge:2", charge).await.unwrap(), 42);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn runs_failed_operations_again() {
+        let idempotency = Idempotency::new(MemoryStore::new());
+        let failed = idempotency
+            .run("charge:1", || async { Err::<i32, _>("card declined") })
+            .await;
+        assert!(matches!(
+            failed,
+            Err(IdempotencyError::Operation("card declined"))
+        ));
+        let charged = idempotency
+            .run("charge:1", || async { Ok::<_, String>(42) })
+            .await;
+        assert_eq!(charged.unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn reloads_recorded_results() {
+        let path = temp_file("reload");
+        let store = FileStore::open(&path).unwrap();
+        store
+            .put("charge:1".to_owned(), "42".to_owned())
+            .await
+            .unwrap();
+        drop(store);
+
+        let idempotency = Idempotency::new(FileStore::open(&path).unwrap());
+        let recorded = idempotency
+            .run("charge:1", || async { Err::<i32, _>("ran again") })
+            .await;
+        assert_eq!(recorded.unwrap(), 42);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        let path = temp_file("invalid");
+        std::fs::write(&path, "not json\n").unwrap();
+        assert!(matches!(
+            FileStore::open(&path),
+            Err(StoreError::Invalid(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
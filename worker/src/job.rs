@@ -0,0 +1,259 @@
+use std::{fmt::Display, ops::Deref, time::Duration};
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use zeebe_client::{
+    api::{ActivatedJob, CompleteJobRequest, FailJobRequest, ThrowErrorRequest},
+    ZeebeClient,
+};
+
+use crate::{
+    variables::{from_variables, VariablesError},
+    ZeebeVariables,
+};
+
+/// A job activated by a worker, as passed to its handler.
+#[derive(Debug, Clone)]
+pub struct Job {
+    inner: ActivatedJob,
+    cancellation: CancellationToken,
+}
+
+impl Job {
+    pub(crate) fn new(inner: ActivatedJob, cancellation: CancellationToken) -> Job {
+        Job {
+            inner,
+            cancellation,
+        }
+    }
+
+    /// Deserializes the job variables.
+    pub fn variables<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(&self.inner.variables)
+    }
+
+    /// Deserializes the job variables, checking first that all variables `T` requires are present.
+    pub fn checked_variables<T: ZeebeVariables + DeserializeOwned>(
+        &self,
+    ) -> Result<T, VariablesError> {
+        from_variables(&self.inner.variables)
+    }
+
+    /// Deserializes the custom headers of the task.
+    pub fn custom_headers<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(&self.inner.custom_headers)
+    }
+
+    /// Fires once the job deadline minus the worker's safety margin is reached. Any outcome
+    /// returned after that is discarded, because the broker may already have handed the job to
+    /// another worker.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    pub fn into_inner(self) -> ActivatedJob {
+        self.inner
+    }
+}
+
+impl Deref for Job {
+    type Target = ActivatedJob;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// What a handler decided to do with a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobOutcome {
+    Complete {
+        variables: String,
+    },
+    Fail {
+        error_message: String,
+        // remaining retries, defaults to one less than the job currently has
+        retries: Option<i32>,
+        retry_back_off: Duration,
+        variables: String,
+    },
+    ThrowError {
+        error_code: String,
+        error_message: String,
+    },
+    /// Fails the job with one retry less because a system the handler depends on is overloaded.
+    /// Workers with [`AdaptiveConcurrency`] take this as a signal to handle fewer jobs at once.
+    ///
+    /// [`AdaptiveConcurrency`]: crate::AdaptiveConcurrency
+    Overloaded {
+        error_message: String,
+        retry_back_off: Duration,
+    },
+}
+
+impl JobOutcome {
+    pub fn complete() -> JobOutcome {
+        JobOutcome::Complete {
+            variables: String::new(),
+        }
+    }
+
+    pub fn complete_with<T: Serialize>(variables: &T) -> Result<JobOutcome, serde_json::Error> {
+        let variables = match serde_json::to_value(variables)? {
+            serde_json::Value::Null => String::new(),
+            value => value.to_string(),
+        };
+        Ok(JobOutcome::Complete { variables })
+    }
+
+    pub fn fail(error_message: impl Into<String>) -> JobOutcome {
+        JobOutcome::Fail {
+            error_message: error_message.into(),
+            retries: None,
+            retry_back_off: Duration::ZERO,
+            variables: String::new(),
+        }
+    }
+
+    pub fn overloaded(error_message: impl Into<String>) -> JobOutcome {
+        JobOutcome::Overloaded {
+            error_message: error_message.into(),
+            retry_back_off: Duration::ZERO,
+        }
+    }
+
+    pub fn throw_error(
+        error_code: impl Into<String>,
+        error_message: impl Into<String>,
+    ) -> JobOutcome {
+        JobOutcome::ThrowError {
+            error_code: error_code.into(),
+            error_message: error_message.into(),
+        }
+    }
+}
+
+/// Converts what a handler returns into a [`JobOutcome`].
+///
+/// `Ok` values are serialized into the variables the job is completed with, errors fail the job
+/// with their message.
+pub trait IntoJobOutcome {
+    fn into_job_outcome(self) -> JobOutcome;
+}
+
+impl IntoJobOutcome for JobOutcome {
+    fn into_job_outcome(self) -> JobOutcome {
+        self
+    }
+}
+
+impl IntoJobOutcome for () {
+    fn into_job_outcome(self) -> JobOutcome {
+        JobOutcome::complete()
+    }
+}
+
+impl<T: Serialize, E: Display> IntoJobOutcome for Result<T, E> {
+    fn into_job_outcome(self) -> JobOutcome {
+        match self {
+            Ok(variables) => JobOutcome::complete_with(&variables).unwrap_or_else(|e| {
+                JobOutcome::fail(format!("failed to serialize job variables: {}", e))
+            }),
+            Err(e) => JobOutcome::fail(e.to_string()),
+        }
+    }
+}
+
+/// The command a worker sends to the gateway for a handled job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobCommand {
+    Complete(CompleteJobRequest),
+    Fail(FailJobRequest),
+    ThrowError(ThrowErrorRequest),
+}
+
+impl JobCommand {
+    pub fn new(job: &ActivatedJob, outcome: JobOutcome) -> JobCommand {
+        match outcome {
+            JobOutcome::Complete { variables } => JobCommand::Complete(CompleteJobRequest {
+                job_key: job.key,
+                variables,
+            }),
+            JobOutcome::Fail {
+                error_message,
+                retries,
+                retry_back_off,
+                variables,
+            } => JobCommand::Fail(FailJobRequest {
+                job_key: job.key,
+                retries: retries.unwrap_or(job.retries - 1).max(0),
+                error_message,
+                retry_back_off: retry_back_off.as_millis() as i64,
+                variables,
+            }),
+            JobOutcome::Overloaded {
+                error_message,
+                retry_back_off,
+            } => JobCommand::Fail(FailJobRequest {
+                job_key: job.key,
+                retries: (job.retries - 1).max(0),
+                error_message,
+                retry_back_off: retry_back_off.as_millis() as i64,
+                variables: String::new(),
+            }),
+            JobOutcome::ThrowError {
+                error_code,
+                error_message,
+            } => JobCommand::ThrowError(ThrowErrorRequest {
+                job_key: job.key,
+                error_code,
+                error_message,
+            }),
+        }
+    }
+
+    /// `"complete"`, `"fail"` or `"error"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobCommand::Complete(_) => "complete",
+            JobCommand::Fail(_) => "fail",
+            JobCommand::ThrowError(_) => "error",
+        }
+    }
+
+    pub fn job_key(&self) -> i64 {
+        match self {
+            JobCommand::Complete(request) => request.job_key,
+            JobCommand::Fail(request) => request.job_key,
+            JobCommand::ThrowError(request) => request.job_key,
+        }
+    }
+
+    pub async fn send(self, client: &mut ZeebeClient) -> Result<(), ReportError> {
+        let job_key = self.job_key();
+        let result = match self {
+            JobCommand::Complete(request) => client.complete_job(request).await.map(|_| ()),
+            JobCommand::Fail(request) => client.fail_job(request).await.map(|_| ()),
+            JobCommand::ThrowError(request) => client.throw_error(request).await.map(|_| ()),
+        };
+        result.map_err(|status| match status.code() {
+            tonic::Code::NotFound => ReportError::JobNotActive(job_key),
+            _ => ReportError::Rpc(status),
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ReportError {
+    /// The job timed out, its process instance was cancelled, or another worker already
+    /// completed it. There is nothing left to report, so this is not an error of the handler.
+    #[error("job {0} is no longer active")]
+    JobNotActive(i64),
+    #[error(transparent)]
+    Rpc(#[from] tonic::Status),
+}
@@ -0,0 +1,257 @@
+//! [`tower`] layers for job handlers, see [`WorkerBuilder::service`](crate::WorkerBuilder::service).
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use tower::{BoxError, Layer, Service};
+
+use crate::{BoxFuture, Job};
+
+#[derive(Error, Debug)]
+pub enum TimeoutError {
+    /// The job deadline minus the worker's safety margin was reached.
+    #[error("job deadline reached")]
+    DeadlineReached,
+    #[error("handler did not finish within {0:?}")]
+    Elapsed(Duration),
+}
+
+/// Aborts handlers once the job deadline is reached, or after a fixed maximum duration if that is
+/// shorter.
+#[derive(Debug, Clone, Default)]
+pub struct DeadlineTimeoutLayer {
+    max: Option<Duration>,
+}
+
+impl DeadlineTimeoutLayer {
+    pub fn new() -> DeadlineTimeoutLayer {
+        DeadlineTimeoutLayer::default()
+    }
+
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = Some(max);
+        self
+    }
+}
+
+impl<S> Layer<S> for DeadlineTimeoutLayer {
+    type Service = DeadlineTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeadlineTimeout {
+            inner,
+            max: self.max,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadlineTimeout<S> {
+    inner: S,
+    max: Option<Duration>,
+}
+
+impl<S> Service<Job> for DeadlineTimeout<S>
+where
+    S: Service<Job>,
+    S::Response: Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<Result<S::Response, BoxError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, job: Job) -> Self::Future {
+        let deadline = job.cancellation_token().clone();
+        let max = self.max;
+        let handling = self.inner.call(job);
+        Box::pin(async move {
+            let elapsed = async {
+                match max {
+                    Some(max) => tokio::time::sleep(max).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                result = handling => result.map_err(Into::into),
+                _ = deadline.cancelled() => Err(TimeoutError::DeadlineReached.into()),
+                _ = elapsed => Err(TimeoutError::Elapsed(max.unwrap_or_default()).into()),
+            }
+        })
+    }
+}
+
+/// Logs every job and its result. Variable values are redacted unless they are explicitly
+/// allowed with [`LoggingLayer::show_variables`].
+#[derive(Debug, Clone, Default)]
+pub struct LoggingLayer {
+    shown: Arc<HashSet<String>>,
+}
+
+impl LoggingLayer {
+    pub fn new() -> LoggingLayer {
+        LoggingLayer::default()
+    }
+
+    pub fn show_variables<I, V>(mut self, variables: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<String>,
+    {
+        self.shown = Arc::new(variables.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl<S> Layer<S> for LoggingLayer {
+    type Service = Logging<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Logging {
+            inner,
+            shown: self.shown.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Logging<S> {
+    inner: S,
+    shown: Arc<HashSet<String>>,
+}
+
+impl<S> Logging<S> {
+    fn redacted_variables(&self, job: &Job) -> String {
+        match serde_json::from_str::<serde_json::Value>(&job.variables) {
+            Ok(serde_json::Value::Object(mut variables)) => {
+                for (name, value) in variables.iter_mut() {
+                    if !self.shown.contains(name) {
+                        *value = serde_json::Value::String("***".to_owned());
+                    }
+                }
+                serde_json::Value::Object(variables).to_string()
+            }
+            _ => "***".to_owned(),
+        }
+    }
+}
+
+impl<S> Service<Job> for Logging<S>
+where
+    S: Service<Job>,
+    S::Response: Send + 'static,
+    S::Error: Display + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, job: Job) -> Self::Future {
+        tracing::info!(
+            job_key = job.key,
+            job_type = %job.r#type,
+            process_instance_key = job.process_instance_key,
+            retries = job.retries,
+            variables = %self.redacted_variables(&job),
+            "handling job"
+        );
+        let job_key = job.key;
+        let started = Instant::now();
+        let handling = self.inner.call(job);
+        Box::pin(async move {
+            let result = handling.await;
+            match &result {
+                Ok(_) => {
+                    tracing::info!(job_key, elapsed = ?started.elapsed(), "job handled")
+                }
+                Err(e) => {
+                    tracing::warn!(job_key, elapsed = ?started.elapsed(), error = %e, "job handler failed")
+                }
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tower::service_fn;
+
+    use super::*;
+    use crate::{testing::TestJob, JobCommand, JobHandler, JobOutcome, ServiceHandler};
+
+    fn sleeping(duration: Duration) -> impl JobHandler {
+        let handler = service_fn(move |_: Job| async move {
+            tokio::time::sleep(duration).await;
+            Ok::<_, BoxError>(JobOutcome::complete())
+        });
+        ServiceHandler::new(
+            DeadlineTimeoutLayer::new()
+                .max(Duration::from_secs(60))
+                .layer(handler),
+        )
+    }
+
+    fn error_message(command: JobCommand) -> String {
+        match command {
+            JobCommand::Fail(request) => request.error_message,
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_handlers_past_the_deadline() {
+        let command = TestJob::new("payment")
+            .cancelled()
+            .run(&sleeping(Duration::from_secs(3600)))
+            .await;
+        assert_eq!(error_message(command), "job deadline reached");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fails_handlers_past_the_maximum_duration() {
+        let command = TestJob::new("payment")
+            .run(&sleeping(Duration::from_secs(3600)))
+            .await;
+        assert_eq!(error_message(command), "handler did not finish within 60s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn passes_on_results_in_time() {
+        let command = TestJob::new("payment")
+            .run(&sleeping(Duration::from_secs(1)))
+            .await;
+        assert_eq!(command.kind(), "complete");
+    }
+
+    #[tokio::test]
+    async fn redacts_variables_not_shown() {
+        let logging = LoggingLayer::new().show_variables(["orderId"]).layer(());
+        let job = TestJob::new("payment")
+            .variables(&json!({ "orderId": "o-1", "cardNumber": "4111 1111 1111 1111" }))
+            .job();
+        assert_eq!(
+            logging.redacted_variables(&job),
+            r#"{"cardNumber":"***","orderId":"o-1"}"#
+        );
+        let job = TestJob::new("payment").variables(&json!([1, 2])).job();
+        assert_eq!(logging.redacted_variables(&job), "***");
+    }
+}
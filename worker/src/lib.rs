@@ -1 +1,34 @@
+mod adaptive;
+mod blocking;
+mod deadline;
+mod duplicates;
+mod handler;
+mod health;
+mod idempotency;
+mod job;
+pub mod layer;
+pub mod metrics;
+mod rate_limit;
+mod registry;
+pub mod testing;
+mod variables;
+mod worker;
 
+pub use adaptive::AdaptiveConcurrency;
+pub use blocking::{BlockingHandler, ThreadPool};
+pub use deadline::DeadlinePolicy;
+pub use duplicates::DuplicateJobs;
+pub use handler::{BoxFuture, JobHandler, ServiceHandler};
+#[cfg(feature = "health")]
+pub use health::HealthServer;
+pub use health::{Health, PollStatus};
+pub use idempotency::{
+    FileStore, Idempotency, IdempotencyError, IdempotencyStore, MemoryStore, StoreError,
+};
+pub use job::{IntoJobOutcome, Job, JobCommand, JobOutcome, ReportError};
+pub use rate_limit::RateLimit;
+pub use registry::{RegistryError, WorkerRegistry, WorkerState};
+pub use variables::{from_variables, VariablesError, ZeebeVariables};
+pub use worker::{Worker, WorkerBuilder, WorkerError};
+pub use zeebe_client;
+pub use zeebe_worker_macros::{job_handler, ZeebeVariables};
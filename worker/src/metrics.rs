@@ -0,0 +1,212 @@
+//! Metrics recorded by workers through the [`metrics`] facade.
+//!
+//! All metrics are labeled with `job_type` and `worker`. Install any `metrics` recorder to
+//! collect them, or enable the `prometheus` feature and call `install_prometheus_exporter`.
+
+use metrics::{Counter, Gauge, Histogram};
+
+pub const JOBS_ACTIVATED: &str = "zeebe_worker_jobs_activated_total";
+pub const JOBS_COMPLETED: &str = "zeebe_worker_jobs_completed_total";
+pub const JOBS_FAILED: &str = "zeebe_worker_jobs_failed_total";
+pub const JOBS_ERROR_THROWN: &str = "zeebe_worker_jobs_error_thrown_total";
+pub const JOBS_STALE: &str = "zeebe_worker_jobs_stale_total";
+pub const JOBS_DUPLICATE: &str = "zeebe_worker_jobs_duplicate_total";
+pub const JOBS_IN_FLIGHT: &str = "zeebe_worker_jobs_in_flight";
+pub const HANDLER_DURATION: &str = "zeebe_worker_handler_duration_seconds";
+pub const ACTIVATION_TO_COMPLETION: &str = "zeebe_worker_activation_to_completion_seconds";
+pub const POLL_LATENCY: &str = "zeebe_worker_poll_latency_seconds";
+pub const POLLS_EMPTY: &str = "zeebe_worker_polls_empty_total";
+pub const CONCURRENCY_LIMIT: &str = "zeebe_worker_concurrency_limit";
+
+/// Registers descriptions for all worker metrics with the installed recorder.
+pub fn describe() {
+    use metrics::{describe_counter, describe_gauge, describe_histogram, Unit};
+
+    describe_counter!(JOBS_ACTIVATED, "Jobs activated by the worker");
+    describe_counter!(JOBS_COMPLETED, "Jobs completed by the worker");
+    describe_counter!(JOBS_FAILED, "Jobs failed by the worker");
+    describe_counter!(JOBS_ERROR_THROWN, "BPMN errors thrown by the worker");
+    describe_counter!(
+        JOBS_STALE,
+        "Job outcomes rejected because the job was no longer active"
+    );
+    describe_counter!(
+        JOBS_DUPLICATE,
+        "Jobs activated again while their handler was still running"
+    );
+    describe_gauge!(JOBS_IN_FLIGHT, "Jobs currently being handled");
+    describe_histogram!(
+        HANDLER_DURATION,
+        Unit::Seconds,
+        "Time spent in the job handler"
+    );
+    describe_histogram!(
+        ACTIVATION_TO_COMPLETION,
+        Unit::Seconds,
+        "Time from job activation until its completion was reported"
+    );
+    describe_histogram!(
+        POLL_LATENCY,
+        Unit::Seconds,
+        "Duration of activate jobs requests"
+    );
+    describe_counter!(POLLS_EMPTY, "Activate jobs requests that returned no jobs");
+    describe_gauge!(
+        CONCURRENCY_LIMIT,
+        "Current limit of jobs in flight with adaptive concurrency"
+    );
+}
+
+/// Serves all metrics in the Prometheus text format on `addr`.
+///
+/// Must be called from within a Tokio runtime.
+#[cfg(feature = "prometheus")]
+pub fn install_prometheus_exporter(
+    addr: std::net::SocketAddr,
+) -> Result<(), metrics_exporter_prometheus::BuildError> {
+    use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .set_buckets_for_metric(
+            Matcher::Suffix("_seconds".to_owned()),
+            &[
+                0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+            ],
+        )?
+        .install()?;
+    describe();
+    Ok(())
+}
+
+/// Metric handles of one worker.
+pub(crate) struct WorkerMetrics {
+    pub(crate) activated: Counter,
+    pub(crate) completed: Counter,
+    pub(crate) failed: Counter,
+    pub(crate) error_thrown: Counter,
+    pub(crate) stale: Counter,
+    pub(crate) duplicate: Counter,
+    pub(crate) in_flight: Gauge,
+    pub(crate) handler_duration: Histogram,
+    pub(crate) activation_to_completion: Histogram,
+    pub(crate) poll_latency: Histogram,
+    pub(crate) polls_empty: Counter,
+    pub(crate) concurrency_limit: Gauge,
+}
+
+impl WorkerMetrics {
+    pub(crate) fn new(job_type: &str, worker: &str) -> WorkerMetrics {
+        let labels = [
+            ("job_type", job_type.to_owned()),
+            ("worker", worker.to_owned()),
+        ];
+        WorkerMetrics {
+            activated: metrics::counter!(JOBS_ACTIVATED, &labels),
+            completed: metrics::counter!(JOBS_COMPLETED, &labels),
+            failed: metrics::counter!(JOBS_FAILED, &labels),
+            error_thrown: metrics::counter!(JOBS_ERROR_THROWN, &labels),
+            stale: metrics::counter!(JOBS_STALE, &labels),
+            duplicate: metrics::counter!(JOBS_DUPLICATE, &labels),
+            in_flight: metrics::gauge!(JOBS_IN_FLIGHT, &labels),
+            handler_duration: metrics::histogram!(HANDLER_DURATION, &labels),
+            activation_to_completion: metrics::histogram!(ACTIVATION_TO_COMPLETION, &labels),
+            poll_latency: metrics::histogram!(POLL_LATENCY, &labels),
+            polls_empty: metrics::counter!(POLLS_EMPTY, &labels),
+            concurrency_limit: metrics::gauge!(CONCURRENCY_LIMIT, &labels),
+        }
+    }
+
+    /// Counts a job as in flight until the returned guard is dropped.
+    pub(crate) fn track_in_flight(&self) -> InFlight {
+        self.in_flight.increment(1.0);
+        InFlight(self.in_flight.clone())
+    }
+}
+
+pub(crate) struct InFlight(Gauge);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.decrement(1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use metrics::{Key, Label};
+    use metrics_util::{
+        debugging::{DebugValue, DebuggingRecorder, Snapshotter},
+        CompositeKey, MetricKind,
+    };
+
+    use super::*;
+
+    /// Takes the values of the metrics of worker `w1` of job type `payment`, by name.
+    fn snapshot(snapshotter: &Snapshotter) -> HashMap<(MetricKind, &'static str), DebugValue> {
+        let mut values = snapshotter.snapshot().into_hashmap();
+        [
+            (MetricKind::Counter, JOBS_COMPLETED),
+            (MetricKind::Gauge, JOBS_IN_FLIGHT),
+            (MetricKind::Histogram, ACTIVATION_TO_COMPLETION),
+        ]
+        .into_iter()
+        .filter_map(|(kind, name)| {
+            let labels = vec![
+                Label::new("job_type", "payment"),
+                Label::new("worker", "w1"),
+            ];
+            let key = CompositeKey::new(kind, Key::from_parts(name, labels));
+            let (_, _, value) = values.remove(&key)?;
+            Some(((kind, name), value))
+        })
+        .collect()
+    }
+
+    fn recorded() -> (DebuggingRecorder, Snapshotter) {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        (recorder, snapshotter)
+    }
+
+    #[test]
+    fn labels_metrics_with_the_worker() {
+        let (recorder, snapshotter) = recorded();
+        let metrics =
+            metrics::with_local_recorder(&recorder, || WorkerMetrics::new("payment", "w1"));
+        metrics.completed.increment(2);
+        metrics.activation_to_completion.record(0.5);
+
+        let values = snapshot(&snapshotter);
+        assert_eq!(
+            values[&(MetricKind::Counter, JOBS_COMPLETED)],
+            DebugValue::Counter(2)
+        );
+        assert_eq!(
+            values[&(MetricKind::Histogram, ACTIVATION_TO_COMPLETION)],
+            DebugValue::Histogram(vec![0.5.into()])
+        );
+    }
+
+    #[test]
+    fn tracks_jobs_in_flight() {
+        let (recorder, snapshotter) = recorded();
+        let metrics =
+            metrics::with_local_recorder(&recorder, || WorkerMetrics::new("payment", "w1"));
+        let in_flight = || {
+            snapshot(&snapshotter)
+                .remove(&(MetricKind::Gauge, JOBS_IN_FLIGHT))
+                .unwrap()
+        };
+
+        let first = metrics.track_in_flight();
+        let second = metrics.track_in_flight();
+        assert_eq!(in_flight(), DebugValue::Gauge(2.0.into()));
+        drop(first);
+        assert_eq!(in_flight(), DebugValue::Gauge(1.0.into()));
+        drop(second);
+        assert_eq!(in_flight(), DebugValue::Gauge(0.0.into()));
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+/// Caps how many jobs a worker activates, and thus how many handlers it starts, per second.
+///
+/// The limit is applied before activating, so throttled jobs stay with the broker instead of
+/// waiting in the worker until they time out. Streamed jobs are activated before the worker sees
+/// them, so rate limits can't be used with [`WorkerBuilder::stream_jobs`].
+///
+/// [`WorkerBuilder::stream_jobs`]: crate::WorkerBuilder::stream_jobs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    per_second: f64,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Allows `per_second` jobs per second, with bursts of up to one second's worth of jobs.
+    pub fn per_second(per_second: f64) -> RateLimit {
+        RateLimit {
+            per_second,
+            burst: (per_second.ceil() as u32).max(1),
+        }
+    }
+
+    pub fn jobs_per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Allows up to `burst` jobs at once after a quiet period.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(limit: RateLimit) -> TokenBucket {
+        TokenBucket {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled_at = now;
+    }
+
+    /// Waits until at least one token is available and returns how many are.
+    pub(crate) async fn available(&mut self) -> usize {
+        loop {
+            self.refill();
+            if self.tokens >= 1.0 {
+                return self.tokens as usize;
+            }
+            let missing = 1.0 - self.tokens;
+            tokio::time::sleep(Duration::from_secs_f64(missing / self.limit.per_second)).await;
+        }
+    }
+
+    pub(crate) fn take(&mut self, tokens: usize) {
+        self.refill();
+        self.tokens -= tokens as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(per_second: f64, burst: u32, tokens: f64, elapsed: Duration) -> TokenBucket {
+        TokenBucket {
+            limit: RateLimit::per_second(per_second).burst(burst),
+            tokens,
+            refilled_at: Instant::now() - elapsed,
+        }
+    }
+
+    #[test]
+    fn bursts_default_to_one_second() {
+        assert_eq!(RateLimit::per_second(2.5).burst, 3);
+        assert_eq!(RateLimit::per_second(0.1).burst, 1);
+        assert_eq!(RateLimit::per_second(10.0).burst(0).burst, 1);
+    }
+
+    #[tokio::test]
+    async fn starts_with_a_full_burst() {
+        let mut bucket = TokenBucket::new(RateLimit::per_second(1.0).burst(5));
+        assert_eq!(bucket.available().await, 5);
+        bucket.take(3);
+        assert_eq!(bucket.available().await, 2);
+    }
+
+    #[tokio::test]
+    async fn refills_with_the_elapsed_time() {
+        let mut bucket = bucket(4.0, 10, 0.0, Duration::from_millis(500));
+        assert_eq!(bucket.available().await, 2);
+    }
+
+    #[tokio::test]
+    async fn refills_up_to_the_burst() {
+        let mut bucket = bucket(4.0, 10, 0.0, Duration::from_secs(60));
+        assert_eq!(bucket.available().await, 10);
+    }
+
+    #[tokio::test]
+    async fn truncates_partial_tokens() {
+        let mut bucket = bucket(0.001, 10, 2.7, Duration::ZERO);
+        assert_eq!(bucket.available().await, 2);
+    }
+
+    #[tokio::test]
+    async fn waits_for_a_token() {
+        let mut bucket = bucket(100.0, 1, 0.0, Duration::ZERO);
+        let started = Instant::now();
+        assert_eq!(bucket.available().await, 1);
+        assert!(started.elapsed() >= Duration::from_millis(9));
+    }
+
+    #[tokio::test]
+    async fn takes_more_than_available() {
+        // jobs activated beyond the tokens are paid back before the next activation
+        let mut bucket = bucket(100.0, 1, 1.0, Duration::ZERO);
+        bucket.take(2);
+        assert!(bucket.tokens < 0.0);
+        let started = Instant::now();
+        assert_eq!(bucket.available().await, 1);
+        assert!(started.elapsed() >= Duration::from_millis(19));
+    }
+}
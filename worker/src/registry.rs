@@ -0,0 +1,334 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use thiserror::Error;
+use tokio::{
+    sync::{watch, Semaphore},
+    task::JoinHandle,
+};
+use tokio_util::sync::CancellationToken;
+use zeebe_client::ZeebeClient;
+
+use crate::{Health, Worker, WorkerBuilder, WorkerError};
+
+#[derive(Error, Debug)]
+pub enum RegistryError {
+    #[error("a worker for job type {0} is already registered")]
+    AlreadyRegistered(String),
+    #[error("no worker registered for job type {0}")]
+    UnknownJobType(String),
+    #[error("max_in_flight must be at least 1")]
+    NoCapacity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerState {
+    Stopped,
+    Running,
+    Paused,
+}
+
+struct Running {
+    shutdown: CancellationToken,
+    paused: watch::Sender<bool>,
+    task: JoinHandle<Result<(), WorkerError>>,
+}
+
+struct Entry {
+    worker: Worker,
+    running: Option<Running>,
+}
+
+/// Runs workers for many job types on one client, optionally sharing a limit on the number of
+/// jobs in flight across all of them.
+pub struct WorkerRegistry {
+    client: ZeebeClient,
+    shared_slots: Option<Arc<Semaphore>>,
+    max_in_flight: Option<usize>,
+    health: Option<Health>,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl WorkerRegistry {
+    pub fn new(client: ZeebeClient) -> WorkerRegistry {
+        WorkerRegistry {
+            client,
+            shared_slots: None,
+            max_in_flight: None,
+            health: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limits the jobs in flight across all registered job types. Each worker is still bound by
+    /// its own `max_jobs_active`.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Result<Self, RegistryError> {
+        if max_in_flight == 0 {
+            return Err(RegistryError::NoCapacity);
+        }
+        self.shared_slots = Some(Arc::new(Semaphore::new(max_in_flight)));
+        self.max_in_flight = Some(max_in_flight);
+        Ok(self)
+    }
+
+    /// Reports the polls of all registered workers to `health`.
+    pub fn health(mut self, health: Health) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Starts a worker builder for `job_type` that uses the registry's client.
+    pub fn worker(&self, job_type: impl Into<String>) -> WorkerBuilder {
+        WorkerBuilder::new(self.client.clone(), job_type)
+    }
+
+    /// Adds a worker without starting it.
+    pub fn register(&self, worker: Worker) -> Result<(), RegistryError> {
+        let mut entries = self.entries.lock().unwrap();
+        let job_type = worker.job_type().to_owned();
+        if entries.contains_key(&job_type) {
+            return Err(RegistryError::AlreadyRegistered(job_type));
+        }
+        entries.insert(
+            job_type,
+            Entry {
+                worker,
+                running: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Starts polling for `job_type`. Starting a running or paused worker does nothing.
+    pub fn start(&self, job_type: &str) -> Result<(), RegistryError> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get_mut(job_type)
+            .ok_or_else(|| RegistryError::UnknownJobType(job_type.to_owned()))?;
+        if let Some(running) = &entry.running {
+            if !running.task.is_finished() {
+                return Ok(());
+            }
+        }
+        let shutdown = CancellationToken::new();
+        let (paused, paused_rx) = watch::channel(false);
+        let worker = entry.worker.fork(
+            shutdown.clone(),
+            paused_rx,
+            self.shared_slots.clone(),
+            self.health.clone(),
+        );
+        entry.running = Some(Running {
+            shutdown,
+            paused,
+            task: tokio::spawn(worker.run()),
+        });
+        Ok(())
+    }
+
+    pub fn start_all(&self) {
+        let job_types: Vec<String> = self.entries.lock().unwrap().keys().cloned().collect();
+        for job_type in job_types {
+            self.start(&job_type)
+                .expect("job type was registered a moment ago");
+        }
+    }
+
+    /// Stops activating new jobs for `job_type`; jobs in flight keep running.
+    pub fn pause(&self, job_type: &str) -> Result<(), RegistryError> {
+        self.set_paused(job_type, true)
+    }
+
+    pub fn resume(&self, job_type: &str) -> Result<(), RegistryError> {
+        self.set_paused(job_type, false)
+    }
+
+    fn set_paused(&self, job_type: &str, paused: bool) -> Result<(), RegistryError> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get(job_type)
+            .ok_or_else(|| RegistryError::UnknownJobType(job_type.to_owned()))?;
+        if let Some(running) = &entry.running {
+            running.paused.send_replace(paused);
+        }
+        Ok(())
+    }
+
+    /// Stops polling for `job_type` and waits for its jobs in flight to finish.
+    pub async fn stop(&self, job_type: &str) -> Result<(), RegistryError> {
+        let running = {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get_mut(job_type)
+                .ok_or_else(|| RegistryError::UnknownJobType(job_type.to_owned()))?;
+            entry.running.take()
+        };
+        if let Some(running) = running {
+            stop(job_type, running).await;
+        }
+        Ok(())
+    }
+
+    pub async fn stop_all(&self) {
+        let running: Vec<(String, Running)> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(|(job_type, entry)| Some((job_type.clone(), entry.running.take()?)))
+            .collect();
+        for (_, running) in &running {
+            running.shutdown.cancel();
+        }
+        for (job_type, running) in running {
+            stop(&job_type, running).await;
+        }
+    }
+
+    pub fn state(&self, job_type: &str) -> Result<WorkerState, RegistryError> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get(job_type)
+            .ok_or_else(|| RegistryError::UnknownJobType(job_type.to_owned()))?;
+        Ok(entry_state(entry))
+    }
+
+    pub fn states(&self) -> BTreeMap<String, WorkerState> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(job_type, entry)| (job_type.clone(), entry_state(entry)))
+            .collect()
+    }
+
+    /// Jobs currently in flight across all job types, if a global limit is configured.
+    pub fn in_flight(&self) -> Option<usize> {
+        let max_in_flight = self.max_in_flight?;
+        let available = self.shared_slots.as_ref()?.available_permits();
+        Some(max_in_flight - available)
+    }
+}
+
+fn entry_state(entry: &Entry) -> WorkerState {
+    match &entry.running {
+        Some(running) if running.task.is_finished() => WorkerState::Stopped,
+        Some(running) if *running.paused.borrow() => WorkerState::Paused,
+        Some(_) => WorkerState::Running,
+        None => WorkerState::Stopped,
+    }
+}
+
+async fn stop(job_type: &str, running: Running) {
+    running.shutdown.cancel();
+    match running.task.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!(job_type, error = ?e, "worker failed"),
+        Err(e) => tracing::error!(job_type, error = ?e, "worker task panicked"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::JobOutcome;
+
+    fn registry() -> WorkerRegistry {
+        let registry = WorkerRegistry::new(crate::testing::client());
+        for job_type in ["payment", "shipping"] {
+            let worker = registry
+                .worker(job_type)
+                .handler(|_| async { JobOutcome::complete() })
+                .build()
+                .unwrap();
+            registry.register(worker).unwrap();
+        }
+        registry
+    }
+
+    #[tokio::test]
+    async fn pauses_resumes_and_stops_workers() {
+        let registry = registry();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Stopped);
+        registry.start("payment").unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Running);
+        registry.pause("payment").unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Paused);
+        // starting a paused worker leaves it paused
+        registry.start("payment").unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Paused);
+        registry.resume("payment").unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Running);
+        registry.stop("payment").await.unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Stopped);
+        // stopped workers can be started again
+        registry.start("payment").unwrap();
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Running);
+        registry.stop_all().await;
+        assert_eq!(registry.state("payment").unwrap(), WorkerState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn stops_paused_workers() {
+        let registry = registry();
+        registry.start_all();
+        registry.pause("shipping").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), registry.stop("shipping"))
+            .await
+            .expect("paused worker stops")
+            .unwrap();
+        assert_eq!(
+            registry.states(),
+            BTreeMap::from([
+                ("payment".to_owned(), WorkerState::Running),
+                ("shipping".to_owned(), WorkerState::Stopped),
+            ])
+        );
+        registry.stop_all().await;
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_job_types() {
+        let registry = registry();
+        assert!(matches!(
+            registry.start("billing"),
+            Err(RegistryError::UnknownJobType(job_type)) if job_type == "billing"
+        ));
+        assert!(registry.pause("billing").is_err());
+        assert!(registry.resume("billing").is_err());
+        assert!(registry.stop("billing").await.is_err());
+        assert!(registry.state("billing").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_registering_a_job_type_twice() {
+        let registry = registry();
+        let worker = registry
+            .worker("payment")
+            .handler(|_| async { JobOutcome::complete() })
+            .build()
+            .unwrap();
+        assert!(matches!(
+            registry.register(worker),
+            Err(RegistryError::AlreadyRegistered(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn needs_capacity() {
+        let registry = WorkerRegistry::new(crate::testing::client());
+        assert!(registry.in_flight().is_none());
+        assert!(matches!(
+            registry.max_in_flight(0),
+            Err(RegistryError::NoCapacity)
+        ));
+        let registry = WorkerRegistry::new(crate::testing::client())
+            .max_in_flight(4)
+            .unwrap();
+        assert_eq!(registry.in_flight(), Some(0));
+    }
+}
@@ -0,0 +1,196 @@
+//! Runs job handlers without a gateway, to unit-test them.
+//!
+//! A [`TestJob`] describes a synthetic activated job. Running a handler or a worker on it returns
+//! the [`JobCommand`] the worker would send to the gateway:
+//!
+//! ```no_run
+//! use serde::{Deserialize, Serialize};
+//! use zeebe_worker::{job_handler, testing::TestJob, JobCommand, ZeebeVariables};
+//!
+//! #[derive(Deserialize, ZeebeVariables)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Payment {
+//!     order_id: String,
+//!     amount: f64,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Receipt {
+//!     charged: bool,
+//! }
+//!
+//! #[job_handler(type = "payment")]
+//! async fn charge(payment: Payment) -> Result<Receipt, String> {
+//!     Ok(Receipt { charged: payment.amount > 0.0 })
+//! }
+//!
+//! #[tokio::test]
+//! async fn charges_the_order() {
+//!     let worker = charge_worker(zeebe_worker::testing::client()).build().unwrap();
+//!     let command = TestJob::new("payment")
+//!         .variables(&serde_json::json!({ "orderId": "o-1", "amount": 42 }))
+//!         .run_worker(&worker)
+//!         .await;
+//!     match command {
+//!         JobCommand::Complete(request) => assert_eq!(request.variables, r#"{"charged":true}"#),
+//!         other => panic!("unexpected command {:?}", other),
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Only the handler of a worker runs, not its dispatch: a job past its
+//! [deadline](TestJob::deadline) or [cancelled](TestJob::cancelled) still reaches the handler,
+//! and neither the worker's [`DeadlinePolicy`](crate::DeadlinePolicy) nor its
+//! [`DuplicateJobs`](crate::DuplicateJobs) policy applies. Test those with a worker against a
+//! gateway.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use zeebe_client::{
+    api::{gateway_client::GatewayClient, ActivatedJob},
+    auth::AuthInterceptor,
+    ZeebeClient,
+};
+
+use crate::{Job, JobCommand, JobHandler, Worker};
+
+/// A client that never connects, to build workers that are only run with [`TestJob::run_worker`].
+///
+/// Must be called from within a Tokio runtime.
+pub fn client() -> ZeebeClient {
+    let channel = tonic::transport::Channel::from_static("http://localhost:26500").connect_lazy();
+    GatewayClient::with_interceptor(channel, AuthInterceptor::none())
+}
+
+/// A synthetic activated job.
+///
+/// By default the job has key 1, three retries, no variables or custom headers, and a deadline
+/// five minutes from now.
+#[derive(Debug, Clone)]
+pub struct TestJob {
+    job: ActivatedJob,
+    cancellation: CancellationToken,
+}
+
+impl TestJob {
+    pub fn new(job_type: impl Into<String>) -> TestJob {
+        let deadline = SystemTime::now() + Duration::from_secs(5 * 60);
+        TestJob {
+            job: ActivatedJob {
+                key: 1,
+                r#type: job_type.into(),
+                worker: "test".to_owned(),
+                retries: 3,
+                deadline: deadline.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64,
+                custom_headers: "{}".to_owned(),
+                variables: "{}".to_owned(),
+                ..Default::default()
+            },
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn key(mut self, key: i64) -> Self {
+        self.job.key = key;
+        self
+    }
+
+    pub fn retries(mut self, retries: i32) -> Self {
+        self.job.retries = retries;
+        self
+    }
+
+    /// Sets the job variables, which must serialize to a JSON object.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `variables` can't be serialized.
+    pub fn variables<T: Serialize>(mut self, variables: &T) -> Self {
+        self.job.variables = serde_json::to_string(variables).expect("variables are serializable");
+        self
+    }
+
+    /// Sets the custom headers of the task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `headers` can't be serialized.
+    pub fn custom_headers<T: Serialize>(mut self, headers: &T) -> Self {
+        self.job.custom_headers =
+            serde_json::to_string(headers).expect("custom headers are serializable");
+        self
+    }
+
+    pub fn bpmn_process_id(mut self, bpmn_process_id: impl Into<String>) -> Self {
+        self.job.bpmn_process_id = bpmn_process_id.into();
+        self
+    }
+
+    pub fn process_instance_key(mut self, process_instance_key: i64) -> Self {
+        self.job.process_instance_key = process_instance_key;
+        self
+    }
+
+    pub fn element_id(mut self, element_id: impl Into<String>) -> Self {
+        self.job.element_id = element_id.into();
+        self
+    }
+
+    /// Sets the job deadline as a UNIX epoch timestamp in milliseconds.
+    pub fn deadline(mut self, deadline: i64) -> Self {
+        self.job.deadline = deadline;
+        self
+    }
+
+    /// Hands the job to the handler already cancelled, as if its deadline had been reached.
+    pub fn cancelled(self) -> Self {
+        self.cancellation.cancel();
+        self
+    }
+
+    /// The job as passed to handlers, to call handler functions directly.
+    pub fn job(&self) -> Job {
+        Job::new(self.job.clone(), self.cancellation.clone())
+    }
+
+    pub fn activated_job(&self) -> &ActivatedJob {
+        &self.job
+    }
+
+    /// Runs `handler` on the job and returns the command reporting its outcome.
+    pub async fn run(&self, handler: &impl JobHandler) -> JobCommand {
+        let outcome = handler.call(self.job()).await;
+        JobCommand::new(&self.job, outcome)
+    }
+
+    /// Runs the handler of `worker` on the job and returns the command reporting its outcome.
+    ///
+    /// Like the gateway, this only passes the variables the worker fetches to the handler. Unlike
+    /// a running worker, it calls the handler even if the job is past its deadline or cancelled.
+    pub async fn run_worker(&self, worker: &Worker) -> JobCommand {
+        let mut job = self.job.clone();
+        job.worker = worker.name().to_owned();
+        job.variables = fetched_variables(&job.variables, worker.fetch_variables());
+        let outcome = worker
+            .handler()
+            .call(Job::new(job.clone(), self.cancellation.clone()))
+            .await;
+        JobCommand::new(&job, outcome)
+    }
+}
+
+fn fetched_variables(variables: &str, fetch: &[String]) -> String {
+    if fetch.is_empty() {
+        return variables.to_owned();
+    }
+    match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(variables) {
+        Ok(mut variables) => {
+            variables.retain(|name, _| fetch.contains(name));
+            serde_json::Value::Object(variables).to_string()
+        }
+        Err(_) => variables.to_owned(),
+    }
+}
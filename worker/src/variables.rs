@@ -0,0 +1,43 @@
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Describes the variables a struct is deserialized from. Usually derived with
+/// `#[derive(ZeebeVariables)]`.
+pub trait ZeebeVariables {
+    /// Names of all variables the struct reads, to be used as `fetch_variable` when activating
+    /// jobs.
+    fn variable_names() -> Vec<String>;
+
+    /// Names of the variables that must be present for deserialization to succeed.
+    fn required_variables() -> Vec<String>;
+}
+
+#[derive(Error, Debug)]
+pub enum VariablesError {
+    #[error("variables are not a JSON object")]
+    NotAnObject,
+    #[error("missing variables: {}", .0.join(", "))]
+    Missing(Vec<String>),
+    #[error(transparent)]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Deserializes `variables`, reporting all missing required variables at once rather than just
+/// the first one.
+pub fn from_variables<T: ZeebeVariables + DeserializeOwned>(
+    variables: &str,
+) -> Result<T, VariablesError> {
+    let variables = match variables {
+        "" => serde_json::Value::Object(Default::default()),
+        variables => serde_json::from_str(variables)?,
+    };
+    let object = variables.as_object().ok_or(VariablesError::NotAnObject)?;
+    let missing: Vec<String> = T::required_variables()
+        .into_iter()
+        .filter(|name| !object.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(VariablesError::Missing(missing));
+    }
+    Ok(serde_json::from_value(variables)?)
+}
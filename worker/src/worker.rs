@@ -0,0 +1,832 @@
+use std::{
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use tokio::{
+    sync::{watch, Mutex, OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tower::Service;
+use tracing::{instrument, Instrument, Level, Span};
+use zeebe_client::{
+    api::{ActivateJobsRequest, ActivatedJob, StreamActivatedJobsRequest},
+    ZeebeClient,
+};
+
+use crate::{
+    adaptive::{AdaptiveConcurrency, Limiter, Signal},
+    blocking::{BlockingHandler, ThreadPool},
+    deadline::Watchdog,
+    duplicates::{DuplicateJobs, InFlightJobs, Registration},
+    handler::ServiceHandler,
+    health::Heartbeat,
+    metrics::WorkerMetrics,
+    rate_limit::TokenBucket,
+    DeadlinePolicy, Health, IntoJobOutcome, Job, JobCommand, JobHandler, JobOutcome, RateLimit,
+    ReportError, ZeebeVariables,
+};
+
+// pause between attempts to reopen a failed job stream, doubled after every failure
+const STREAM_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const STREAM_BACKOFF_MAX: Duration = Duration::from_secs(30);
+// while the job stream is open, polling only picks up jobs that became activatable before it
+// was opened or while it was reconnecting
+const STREAMING_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// `tracing` only accepts constant levels in its macros.
+macro_rules! event_at {
+    ($level:expr, $($args:tt)+) => {
+        match $level {
+            Level::ERROR => tracing::error!($($args)+),
+            Level::WARN => tracing::warn!($($args)+),
+            Level::INFO => tracing::info!($($args)+),
+            Level::DEBUG => tracing::debug!($($args)+),
+            Level::TRACE => tracing::trace!($($args)+),
+        }
+    };
+}
+
+#[derive(Error, Debug)]
+pub enum WorkerError {
+    #[error("no handler configured for job type {0}")]
+    MissingHandler(String),
+    #[error("max_jobs_active must be at least 1")]
+    NoCapacity,
+    #[error("rate limit must allow a positive number of jobs per second")]
+    InvalidRateLimit,
+    #[error("adaptive concurrency needs 1 <= min <= max and a backoff ratio between 0 and 1")]
+    InvalidAdaptiveConcurrency,
+    #[error(
+        "rate limits can't be combined with job streaming, streamed jobs are already activated"
+    )]
+    RateLimitedStream,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorkerConfig {
+    pub(crate) job_type: String,
+    pub(crate) name: String,
+    pub(crate) timeout: Duration,
+    pub(crate) max_jobs_active: usize,
+    pub(crate) poll_interval: Duration,
+    pub(crate) request_timeout: Duration,
+    pub(crate) fetch_variables: Vec<String>,
+    pub(crate) deadline: DeadlinePolicy,
+    pub(crate) stale_job_log_level: Level,
+    pub(crate) rate_limit: Option<RateLimit>,
+    pub(crate) adaptive: Option<AdaptiveConcurrency>,
+    pub(crate) stream: bool,
+    pub(crate) duplicate_jobs: DuplicateJobs,
+}
+
+pub struct WorkerBuilder {
+    client: ZeebeClient,
+    config: WorkerConfig,
+    handler: Option<Arc<dyn JobHandler>>,
+    health: Option<Health>,
+}
+
+impl WorkerBuilder {
+    pub fn new(client: ZeebeClient, job_type: impl Into<String>) -> WorkerBuilder {
+        WorkerBuilder {
+            client,
+            config: WorkerConfig {
+                job_type: job_type.into(),
+                name: "default".to_owned(),
+                timeout: Duration::from_secs(5 * 60),
+                max_jobs_active: 32,
+                poll_interval: Duration::from_millis(100),
+                request_timeout: Duration::from_secs(10),
+                fetch_variables: vec![],
+                deadline: DeadlinePolicy::default(),
+                stale_job_log_level: Level::INFO,
+                rate_limit: None,
+                adaptive: None,
+                stream: false,
+                duplicate_jobs: DuplicateJobs::default(),
+            },
+            handler: None,
+            health: None,
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = name.into();
+        self
+    }
+
+    /// How long jobs stay locked to this worker after activation.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn max_jobs_active(mut self, max_jobs_active: usize) -> Self {
+        self.config.max_jobs_active = max_jobs_active;
+        self
+    }
+
+    /// Pause between polls that returned no jobs or failed.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.config.poll_interval = poll_interval;
+        self
+    }
+
+    /// How long the gateway holds an activation request open while no jobs are available.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.config.request_timeout = request_timeout;
+        self
+    }
+
+    pub fn fetch_variables(mut self, fetch_variables: Vec<String>) -> Self {
+        self.config.fetch_variables = fetch_variables;
+        self
+    }
+
+    /// Fetches only the variables `T` is deserialized from.
+    pub fn fetch_variables_of<T: ZeebeVariables>(self) -> Self {
+        self.fetch_variables(T::variable_names())
+    }
+
+    pub fn deadline_policy(mut self, deadline: DeadlinePolicy) -> Self {
+        self.config.deadline = deadline;
+        self
+    }
+
+    /// Level at which outcomes rejected because the job is no longer active are logged.
+    pub fn stale_job_log_level(mut self, level: Level) -> Self {
+        self.config.stale_job_log_level = level;
+        self
+    }
+
+    /// Adjusts the number of jobs in flight to the observed handler latency and backpressure,
+    /// starting at `max_jobs_active`.
+    pub fn adaptive_concurrency(mut self, adaptive: AdaptiveConcurrency) -> Self {
+        self.config.adaptive = Some(adaptive);
+        self
+    }
+
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.config.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// What to do with jobs that are activated again while their handler is still running.
+    pub fn duplicate_jobs(mut self, duplicate_jobs: DuplicateJobs) -> Self {
+        self.config.duplicate_jobs = duplicate_jobs;
+        self
+    }
+
+    /// Receives jobs as they become activatable through a job stream, in addition to polling.
+    ///
+    /// While the stream is open, polls that return no jobs are followed by a longer pause. The
+    /// stream is reopened with backoff when it fails; against gateways that don't support job
+    /// streaming, the worker keeps polling as usual.
+    ///
+    /// Streamed jobs are activated by the broker before they reach the worker, so the stream is
+    /// closed while all job slots are busy, and the open stream holds one slot for the next job.
+    /// This can't be combined with [`WorkerBuilder::rate_limit`], which only works by not
+    /// activating jobs.
+    pub fn stream_jobs(mut self, stream: bool) -> Self {
+        self.config.stream = stream;
+        self
+    }
+
+    /// Reports the result of every poll to `health`, e.g. to serve it to Kubernetes probes.
+    pub fn health(mut self, health: Health) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    pub fn handler(mut self, handler: impl JobHandler) -> Self {
+        self.handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Handles jobs with a synchronous function on Tokio's blocking threads.
+    pub fn blocking_handler<F, R>(self, handler: F) -> Self
+    where
+        F: Fn(Job) -> R + Send + Sync + 'static,
+        R: IntoJobOutcome + Send + 'static,
+    {
+        self.handler(BlockingHandler::new(handler, None))
+    }
+
+    /// Handles jobs with a synchronous function on a dedicated thread pool, which also bounds
+    /// how many of them run at once.
+    pub fn blocking_handler_on<F, R>(self, pool: ThreadPool, handler: F) -> Self
+    where
+        F: Fn(Job) -> R + Send + Sync + 'static,
+        R: IntoJobOutcome + Send + 'static,
+    {
+        self.handler(BlockingHandler::new(handler, Some(pool)))
+    }
+
+    /// Handles jobs with a [`tower::Service`], so that handlers can be composed from layers such
+    /// as those in [`crate::layer`]. Errors returned by the service fail the job.
+    pub fn service<S>(self, service: S) -> Self
+    where
+        S: Service<Job> + Clone + Send + 'static,
+        S::Response: IntoJobOutcome,
+        S::Error: Display,
+        S::Future: Send + 'static,
+    {
+        self.handler(ServiceHandler::new(service))
+    }
+
+    pub fn build(self) -> Result<Worker, WorkerError> {
+        let handler = self
+            .handler
+            .ok_or_else(|| WorkerError::MissingHandler(self.config.job_type.clone()))?;
+        if self.config.max_jobs_active == 0 {
+            return Err(WorkerError::NoCapacity);
+        }
+        if let Some(adaptive) = &self.config.adaptive {
+            if !adaptive.is_valid() {
+                return Err(WorkerError::InvalidAdaptiveConcurrency);
+            }
+        }
+        if let Some(rate_limit) = &self.config.rate_limit {
+            if !(rate_limit.jobs_per_second().is_finite() && rate_limit.jobs_per_second() > 0.0) {
+                return Err(WorkerError::InvalidRateLimit);
+            }
+            if self.config.stream {
+                return Err(WorkerError::RateLimitedStream);
+            }
+        }
+        let (_, paused) = watch::channel(false);
+        Ok(Worker {
+            client: self.client,
+            config: Arc::new(self.config),
+            handler,
+            shutdown: CancellationToken::new(),
+            paused,
+            shared_slots: None,
+            health: self.health,
+        })
+    }
+}
+
+/// Activates jobs of a single type and runs them through its handler.
+pub struct Worker {
+    client: ZeebeClient,
+    config: Arc<WorkerConfig>,
+    handler: Arc<dyn JobHandler>,
+    shutdown: CancellationToken,
+    paused: watch::Receiver<bool>,
+    // in-flight limit shared with other workers, see `WorkerRegistry`
+    shared_slots: Option<Arc<Semaphore>>,
+    health: Option<Health>,
+}
+
+/// Per-run state used by both the polling loop and the job stream.
+#[derive(Clone)]
+struct Capacity {
+    slots: Arc<Semaphore>,
+    metrics: Arc<WorkerMetrics>,
+    limiter: Option<Arc<Limiter>>,
+    rate_limit: Option<Arc<Mutex<TokenBucket>>>,
+    in_flight: InFlightJobs,
+}
+
+/// Capacity for one job, held until the job is handled.
+struct Slot {
+    _local: OwnedSemaphorePermit,
+    _shared: Option<OwnedSemaphorePermit>,
+}
+
+impl Worker {
+    /// Cancelling the returned token stops polling; [`Worker::run`] returns once the jobs in
+    /// flight are done.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub fn job_type(&self) -> &str {
+        &self.config.job_type
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub(crate) fn fetch_variables(&self) -> &[String] {
+        &self.config.fetch_variables
+    }
+
+    pub(crate) fn handler(&self) -> &Arc<dyn JobHandler> {
+        &self.handler
+    }
+
+    /// A fresh copy of this worker that can be run independently of it.
+    pub(crate) fn fork(
+        &self,
+        shutdown: CancellationToken,
+        paused: watch::Receiver<bool>,
+        shared_slots: Option<Arc<Semaphore>>,
+        health: Option<Health>,
+    ) -> Worker {
+        Worker {
+            client: self.client.clone(),
+            config: self.config.clone(),
+            handler: self.handler.clone(),
+            shutdown,
+            paused,
+            shared_slots,
+            health: health.or_else(|| self.health.clone()),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self), fields(job_type = %self.config.job_type, worker = %self.config.name))]
+    pub async fn run(mut self) -> Result<(), WorkerError> {
+        let mut tasks = JoinSet::new();
+        let shutdown = self.shutdown.clone();
+        // created here rather than when building so that a recorder installed in the meantime
+        // is picked up
+        let metrics = Arc::new(WorkerMetrics::new(&self.config.job_type, &self.config.name));
+
+        let limiter = self.config.adaptive.clone().map(|adaptive| {
+            Arc::new(Limiter::new(
+                adaptive,
+                self.config.max_jobs_active,
+                metrics.concurrency_limit.clone(),
+            ))
+        });
+        let capacity = Capacity {
+            slots: match &limiter {
+                Some(limiter) => limiter.slots(),
+                None => Arc::new(Semaphore::new(self.config.max_jobs_active)),
+            },
+            metrics,
+            limiter,
+            rate_limit: self
+                .config
+                .rate_limit
+                .map(|rate_limit| Arc::new(Mutex::new(TokenBucket::new(rate_limit)))),
+            in_flight: InFlightJobs::default(),
+        };
+        let Capacity {
+            slots,
+            metrics,
+            rate_limit,
+            ..
+        } = &capacity;
+
+        let (streaming, mut stream_open) = watch::channel(false);
+        let stream = match self.config.stream {
+            true => {
+                let worker = self.fork(
+                    shutdown.clone(),
+                    self.paused.clone(),
+                    self.shared_slots.clone(),
+                    self.health.clone(),
+                );
+                Some(tokio::spawn(
+                    worker
+                        .stream_jobs(capacity.clone(), streaming)
+                        .in_current_span(),
+                ))
+            }
+            false => None,
+        };
+
+        let heartbeat = Heartbeat::new(self.health.clone(), &self.config.job_type);
+        heartbeat
+            .run(async {
+                while !shutdown.is_cancelled() {
+                    self.wait_while_paused().await;
+                    // wait for the rate limit before taking capacity that other job types could
+                    // use
+                    if let Some(bucket) = rate_limit {
+                        tokio::select! {
+                            _ = async { bucket.lock().await.available().await } => {},
+                            _ = shutdown.cancelled() => break,
+                        }
+                    }
+                    let first = tokio::select! {
+                        slot = self.acquire_slot(slots) => slot,
+                        _ = shutdown.cancelled() => break,
+                    };
+                    let mut free = vec![first];
+                    while let Some(slot) = self.try_acquire_slot(slots) {
+                        free.push(slot);
+                    }
+                    if let Some(bucket) = rate_limit {
+                        free.truncate(bucket.lock().await.available().await);
+                    }
+
+                    // the stream delivers new jobs, so don't hold capacity for a long poll
+                    // meanwhile
+                    let long_poll = !*stream_open.borrow();
+                    let polled_at = Instant::now();
+                    let jobs = tokio::select! {
+                        jobs = self.activate_jobs(free.len(), long_poll) => jobs,
+                        _ = shutdown.cancelled() => break,
+                    };
+                    metrics
+                        .poll_latency
+                        .record(polled_at.elapsed().as_secs_f64());
+                    if let Some(health) = &self.health {
+                        health.record(&self.config.job_type, jobs.as_ref().map(Vec::len));
+                    }
+                    match jobs {
+                        Ok(jobs) if jobs.is_empty() => {
+                            metrics.polls_empty.increment(1);
+                            drop(free);
+                            self.back_off().await;
+                            self.wait_while_streaming(&mut stream_open).await;
+                        }
+                        Ok(jobs) => {
+                            if let Some(bucket) = rate_limit {
+                                bucket.lock().await.take(jobs.len());
+                            }
+                            let activated_at = Instant::now();
+                            metrics.activated.increment(jobs.len() as u64);
+                            for job in jobs {
+                                let slot = match free.pop() {
+                                    Some(slot) => slot,
+                                    None => self.acquire_slot(slots).await,
+                                };
+                                tasks.spawn(self.dispatcher(&capacity).handle(
+                                    job,
+                                    slot,
+                                    activated_at,
+                                ));
+                            }
+                        }
+                        Err(status) => {
+                            tracing::warn!(error = ?status, "failed to activate jobs");
+                            drop(free);
+                            self.back_off().await;
+                        }
+                    }
+
+                    while let Some(result) = tasks.try_join_next() {
+                        log_panic(result);
+                    }
+                }
+
+                while let Some(result) = tasks.join_next().await {
+                    log_panic(result);
+                }
+            })
+            .await;
+        if let Some(stream) = stream {
+            if let Err(e) = stream.await {
+                tracing::error!(error = ?e, "job stream panicked");
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps a job stream open and handles the jobs it delivers until the worker shuts down.
+    async fn stream_jobs(mut self, capacity: Capacity, streaming: watch::Sender<bool>) {
+        let mut tasks = JoinSet::new();
+        let shutdown = self.shutdown.clone();
+        let mut retry_in = STREAM_BACKOFF_MIN;
+
+        while !shutdown.is_cancelled() {
+            self.wait_while_paused().await;
+            // the stream stays closed while there is no capacity for the jobs it would push
+            let slot = tokio::select! {
+                slot = self.acquire_slot(&capacity.slots) => slot,
+                _ = shutdown.cancelled() => break,
+            };
+            let request = StreamActivatedJobsRequest {
+                r#type: self.config.job_type.clone(),
+                worker: self.config.name.clone(),
+                timeout: self.config.timeout.as_millis() as i64,
+                fetch_variable: self.config.fetch_variables.clone(),
+            };
+            let opened = tokio::select! {
+                opened = self.client.stream_activated_jobs(request) => opened,
+                _ = shutdown.cancelled() => break,
+            };
+            let result = match opened {
+                Ok(response) => {
+                    tracing::debug!("opened job stream");
+                    streaming.send_replace(true);
+                    retry_in = STREAM_BACKOFF_MIN;
+                    let result = self
+                        .receive_jobs(response.into_inner(), slot, &capacity, &mut tasks)
+                        .await;
+                    streaming.send_replace(false);
+                    result
+                }
+                Err(status) => Err(status),
+            };
+            match result {
+                Ok(()) => tracing::debug!("closed job stream"),
+                Err(status) if status.code() == tonic::Code::Unimplemented => {
+                    tracing::info!("gateway does not support job streaming, only polling");
+                    break;
+                }
+                Err(status) => {
+                    tracing::warn!(error = ?status, ?retry_in, "job stream failed");
+                    tokio::select! {
+                        _ = tokio::time::sleep(retry_in) => {}
+                        _ = shutdown.cancelled() => {}
+                    }
+                    retry_in = (retry_in * 2).min(STREAM_BACKOFF_MAX);
+                }
+            }
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            log_panic(result);
+        }
+    }
+
+    /// Handles streamed jobs until the stream fails, the worker is paused or shut down, or all
+    /// job slots are busy.
+    ///
+    /// The stream is only read while holding a free slot, so every pushed job is handled right
+    /// away. Closing it at capacity makes the gateway hand further jobs to other workers rather
+    /// than leaving them activated in this one's buffers until they time out.
+    async fn receive_jobs(
+        &mut self,
+        mut stream: tonic::Streaming<ActivatedJob>,
+        mut slot: Slot,
+        capacity: &Capacity,
+        tasks: &mut JoinSet<()>,
+    ) -> Result<(), tonic::Status> {
+        let shutdown = self.shutdown.clone();
+        loop {
+            let job = tokio::select! {
+                job = stream.message() => job?,
+                Ok(_) = self.paused.wait_for(|paused| *paused) => return Ok(()),
+                _ = shutdown.cancelled() => return Ok(()),
+            };
+            let job =
+                job.ok_or_else(|| tonic::Status::unavailable("gateway closed the job stream"))?;
+            capacity.metrics.activated.increment(1);
+            tasks.spawn(self.dispatcher(capacity).handle(job, slot, Instant::now()));
+
+            while let Some(result) = tasks.try_join_next() {
+                log_panic(result);
+            }
+            slot = match self.try_acquire_slot(&capacity.slots) {
+                Some(slot) => slot,
+                None => {
+                    tracing::debug!("closing job stream while all job slots are busy");
+                    return Ok(());
+                }
+            };
+        }
+    }
+
+    async fn activate_jobs(
+        &mut self,
+        max_jobs: usize,
+        long_poll: bool,
+    ) -> Result<Vec<ActivatedJob>, tonic::Status> {
+        let request = ActivateJobsRequest {
+            r#type: self.config.job_type.clone(),
+            worker: self.config.name.clone(),
+            timeout: self.config.timeout.as_millis() as i64,
+            max_jobs_to_activate: max_jobs as i32,
+            fetch_variable: self.config.fetch_variables.clone(),
+            request_timeout: match long_poll {
+                true => self.config.request_timeout.as_millis() as i64,
+                false => -1,
+            },
+        };
+        let mut stream = self.client.activate_jobs(request).await?.into_inner();
+        let mut jobs = Vec::with_capacity(max_jobs);
+        while let Some(response) = stream.message().await? {
+            jobs.extend(response.jobs);
+        }
+        Ok(jobs)
+    }
+
+    async fn wait_while_paused(&mut self) {
+        if !*self.paused.borrow() {
+            return;
+        }
+        if let Some(health) = &self.health {
+            health.set_paused(&self.config.job_type, true);
+        }
+        while *self.paused.borrow_and_update() {
+            tracing::debug!("worker paused");
+            tokio::select! {
+                changed = self.paused.changed() => if changed.is_err() { break },
+                _ = self.shutdown.cancelled() => break,
+            }
+        }
+        if let Some(health) = &self.health {
+            health.set_paused(&self.config.job_type, false);
+        }
+    }
+
+    async fn acquire_slot(&self, slots: &Arc<Semaphore>) -> Slot {
+        let local = slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("job slots are never closed");
+        let shared = match &self.shared_slots {
+            Some(shared) => Some(
+                shared
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("job slots are never closed"),
+            ),
+            None => None,
+        };
+        Slot {
+            _local: local,
+            _shared: shared,
+        }
+    }
+
+    fn try_acquire_slot(&self, slots: &Arc<Semaphore>) -> Option<Slot> {
+        let local = slots.clone().try_acquire_owned().ok()?;
+        let shared = match &self.shared_slots {
+            Some(shared) => Some(shared.clone().try_acquire_owned().ok()?),
+            None => None,
+        };
+        Some(Slot {
+            _local: local,
+            _shared: shared,
+        })
+    }
+
+    fn dispatcher(&self, capacity: &Capacity) -> Dispatcher {
+        Dispatcher {
+            client: self.client.clone(),
+            config: self.config.clone(),
+            handler: self.handler.clone(),
+            metrics: capacity.metrics.clone(),
+            limiter: capacity.limiter.clone(),
+            in_flight: capacity.in_flight.clone(),
+        }
+    }
+
+    /// Waits until the job stream closes, or for a while if it stays open.
+    async fn wait_while_streaming(&self, stream_open: &mut watch::Receiver<bool>) {
+        if !*stream_open.borrow_and_update() {
+            return;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(STREAMING_POLL_INTERVAL) => {}
+            _ = stream_open.wait_for(|open| !open) => {}
+            _ = self.shutdown.cancelled() => {}
+        }
+    }
+
+    async fn back_off(&self) {
+        tokio::select! {
+            _ = tokio::time::sleep(self.config.poll_interval) => {}
+            _ = self.shutdown.cancelled() => {}
+        }
+    }
+}
+
+/// Everything needed to handle a single job.
+struct Dispatcher {
+    client: ZeebeClient,
+    config: Arc<WorkerConfig>,
+    handler: Arc<dyn JobHandler>,
+    metrics: Arc<WorkerMetrics>,
+    limiter: Option<Arc<Limiter>>,
+    in_flight: InFlightJobs,
+}
+
+impl Dispatcher {
+    async fn handle(self, job: ActivatedJob, slot: Slot, activated_at: Instant) {
+        let span = tracing::info_span!(
+            "job",
+            "job.key" = job.key,
+            "job.type" = %job.r#type,
+            bpmn_process_id = %job.bpmn_process_id,
+            process_instance_key = job.process_instance_key,
+            element_id = %job.element_id,
+            retries = job.retries,
+            worker = %self.config.name,
+            outcome = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        self.handle_in_span(job, slot, activated_at)
+            .instrument(span)
+            .await
+    }
+
+    async fn handle_in_span(mut self, job: ActivatedJob, _slot: Slot, activated_at: Instant) {
+        let span = Span::current();
+        let _in_flight = self.metrics.track_in_flight();
+        let watchdog = Watchdog::start(job.deadline, self.config.deadline.margin);
+        if watchdog.expired() {
+            span.record("outcome", "skipped");
+            tracing::warn!(
+                deadline = job.deadline,
+                "skipping job, its deadline has already passed"
+            );
+            return;
+        }
+        let _registered = match self.in_flight.register(
+            job.key,
+            job.deadline,
+            watchdog.extender(),
+            self.config.duplicate_jobs,
+        ) {
+            Registration::Registered(guard) => guard,
+            Registration::Duplicate => {
+                self.metrics.duplicate.increment(1);
+                span.record("outcome", "duplicate");
+                tracing::info!(
+                    policy = ?self.config.duplicate_jobs,
+                    "job activated again while its handler is still running, not handling it twice"
+                );
+                return;
+            }
+        };
+
+        let activated = ActivatedJob {
+            variables: String::new(),
+            custom_headers: String::new(),
+            ..job.clone()
+        };
+        let started = Instant::now();
+        let handling = self.handler.call(Job::new(job, watchdog.token()));
+        let outcome = if self.config.deadline.abort_handler {
+            let token = watchdog.token();
+            tokio::select! {
+                outcome = handling => Some(outcome),
+                _ = token.cancelled() => None,
+            }
+        } else {
+            Some(handling.await)
+        };
+        let latency = started.elapsed();
+        self.metrics.handler_duration.record(latency.as_secs_f64());
+        span.record("latency_ms", latency.as_millis() as u64);
+
+        let outcome = match outcome {
+            Some(outcome) if !watchdog.expired() => outcome,
+            _ => {
+                self.sample(Signal::Overloaded);
+                span.record("outcome", "deadline_reached");
+                tracing::warn!(
+                    deadline = activated.deadline,
+                    aborted = self.config.deadline.abort_handler,
+                    "job deadline reached before the handler finished, not reporting an outcome"
+                );
+                return;
+            }
+        };
+
+        let overloaded = matches!(outcome, JobOutcome::Overloaded { .. });
+        let command = JobCommand::new(&activated, outcome);
+        span.record("outcome", command.kind());
+        let completed = matches!(command, JobCommand::Complete(_));
+        let reported = match &command {
+            JobCommand::Complete(_) => &self.metrics.completed,
+            JobCommand::Fail(_) => &self.metrics.failed,
+            JobCommand::ThrowError(_) => &self.metrics.error_thrown,
+        }
+        .clone();
+        let result = command.send(&mut self.client).await;
+        self.sample(Signal::reported(latency, overloaded, &result));
+        match result {
+            Ok(()) => {
+                reported.increment(1);
+                if completed {
+                    self.metrics
+                        .activation_to_completion
+                        .record(activated_at.elapsed().as_secs_f64());
+                }
+            }
+            Err(ReportError::JobNotActive(_)) => {
+                self.metrics.stale.increment(1);
+                event_at!(
+                    self.config.stale_job_log_level,
+                    "job is no longer active, dropping its outcome"
+                );
+            }
+            Err(ReportError::Rpc(status)) => {
+                tracing::error!(error = ?status, "failed to report job outcome");
+            }
+        }
+    }
+}
+
+impl Dispatcher {
+    fn sample(&self, signal: Signal) {
+        if let Some(limiter) = &self.limiter {
+            limiter.sample(signal);
+        }
+    }
+}
+
+fn log_panic(result: Result<(), tokio::task::JoinError>) {
+    if let Err(e) = result {
+        if e.is_panic() {
+            tracing::error!(error = ?e, "job handler panicked");
+        }
+    }
+}
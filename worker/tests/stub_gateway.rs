@@ -0,0 +1,494 @@
+//! Runs workers against an in-process stub gateway.
+//!
+//! The stub pushes a few jobs through `StreamActivatedJobs` and breaks the first stream to make
+//! the worker reconnect. In legacy mode it behaves like a gateway that predates job streaming, so
+//! the worker falls back to polling.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_stream::{wrappers::TcpListenerStream, Stream};
+use tonic::{transport::Server, Request, Response, Status};
+use zeebe_client::{api::*, Authentication, Connection, ZeebeClient};
+use zeebe_worker::{
+    IntoJobOutcome, Job, JobOutcome, RateLimit, Worker, WorkerBuilder, WorkerError, WorkerRegistry,
+};
+
+const JOBS: i64 = 5;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// How the stub gateway behaves.
+#[derive(Clone, Copy)]
+struct Stub {
+    /// Behaves like a gateway that predates job streaming.
+    legacy: bool,
+    /// Deadline of the jobs, as a UNIX epoch timestamp in milliseconds.
+    deadline: i64,
+    /// Rejects completions with `NOT_FOUND`, like a gateway whose jobs timed out meanwhile.
+    stale: bool,
+}
+
+impl Default for Stub {
+    fn default() -> Self {
+        Stub {
+            legacy: false,
+            deadline: i64::MAX,
+            stale: false,
+        }
+    }
+}
+
+struct StubGateway {
+    stub: Stub,
+    pending: Arc<Mutex<VecDeque<i64>>>,
+    broken_once: AtomicBool,
+    completed: mpsc::Sender<i64>,
+}
+
+/// Streamed jobs that were not received when the stream closed become activatable again, like
+/// a gateway yields jobs it could not push.
+struct JobStream {
+    jobs: mpsc::Receiver<Result<ActivatedJob, Status>>,
+    pending: Arc<Mutex<VecDeque<i64>>>,
+}
+
+impl Stream for JobStream {
+    type Item = Result<ActivatedJob, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.jobs.poll_recv(cx)
+    }
+}
+
+impl Drop for JobStream {
+    fn drop(&mut self) {
+        self.jobs.close();
+        while let Ok(job) = self.jobs.try_recv() {
+            if let Ok(job) = job {
+                self.pending.lock().unwrap().push_front(job.key);
+            }
+        }
+    }
+}
+
+fn job(key: i64, deadline: i64) -> ActivatedJob {
+    ActivatedJob {
+        key,
+        r#type: "stub".to_owned(),
+        bpmn_process_id: "stub-process".to_owned(),
+        retries: 3,
+        deadline,
+        custom_headers: "{}".to_owned(),
+        variables: format!(r#"{{"n":{}}}"#, key),
+        ..Default::default()
+    }
+}
+
+#[tonic::async_trait]
+impl gateway_server::Gateway for StubGateway {
+    type ActivateJobsStream = ResponseStream<ActivateJobsResponse>;
+    type StreamActivatedJobsStream = ResponseStream<ActivatedJob>;
+
+    async fn activate_jobs(
+        &self,
+        request: Request<ActivateJobsRequest>,
+    ) -> Result<Response<Self::ActivateJobsStream>, Status> {
+        let request = request.into_inner();
+        let max = request.max_jobs_to_activate as usize;
+        let jobs = match self.stub.legacy {
+            true => {
+                let mut pending = self.pending.lock().unwrap();
+                let n = max.min(pending.len());
+                pending
+                    .drain(..n)
+                    .map(|key| job(key, self.stub.deadline))
+                    .collect()
+            }
+            false => Vec::new(),
+        };
+        if jobs.is_empty() && request.request_timeout >= 0 {
+            // long polling
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        let response = ActivateJobsResponse { jobs };
+        Ok(Response::new(Box::pin(tokio_stream::once(Ok(response)))))
+    }
+
+    async fn stream_activated_jobs(
+        &self,
+        _: Request<StreamActivatedJobsRequest>,
+    ) -> Result<Response<Self::StreamActivatedJobsStream>, Status> {
+        if self.stub.legacy {
+            return Err(Status::unimplemented("StreamActivatedJobs"));
+        }
+        let break_after = match self.broken_once.swap(true, Ordering::SeqCst) {
+            false => Some(2),
+            true => None,
+        };
+        let (jobs, stream) = mpsc::channel(1);
+        let pending = self.pending.clone();
+        let deadline = self.stub.deadline;
+        tokio::spawn(async move {
+            for pushed in 0.. {
+                if break_after == Some(pushed) {
+                    let _ = jobs
+                        .send(Err(Status::unavailable("stub broke the stream")))
+                        .await;
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let slot = match jobs.reserve().await {
+                    Ok(slot) => slot,
+                    Err(_) => return,
+                };
+                // keep the stream open like a gateway waiting for new jobs
+                if let Some(key) = pending.lock().unwrap().pop_front() {
+                    slot.send(Ok(job(key, deadline)));
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(JobStream {
+            jobs: stream,
+            pending: self.pending.clone(),
+        })))
+    }
+
+    async fn complete_job(
+        &self,
+        request: Request<CompleteJobRequest>,
+    ) -> Result<Response<CompleteJobResponse>, Status> {
+        let job_key = request.get_ref().job_key;
+        let _ = self.completed.send(job_key).await;
+        match self.stub.stale {
+            true => Err(Status::not_found(format!(
+                "job {} is not activated",
+                job_key
+            ))),
+            false => Ok(Response::new(CompleteJobResponse {})),
+        }
+    }
+
+    async fn cancel_process_instance(
+        &self,
+        _: Request<CancelProcessInstanceRequest>,
+    ) -> Result<Response<CancelProcessInstanceResponse>, Status> {
+        Err(Status::unimplemented("cancel_process_instance"))
+    }
+
+    async fn create_process_instance(
+        &self,
+        _: Request<CreateProcessInstanceRequest>,
+    ) -> Result<Response<CreateProcessInstanceResponse>, Status> {
+        Err(Status::unimplemented("create_process_instance"))
+    }
+
+    async fn create_process_instance_with_result(
+        &self,
+        _: Request<CreateProcessInstanceWithResultRequest>,
+    ) -> Result<Response<CreateProcessInstanceWithResultResponse>, Status> {
+        Err(Status::unimplemented("create_process_instance_with_result"))
+    }
+
+    async fn deploy_process(
+        &self,
+        _: Request<DeployProcessRequest>,
+    ) -> Result<Response<DeployProcessResponse>, Status> {
+        Err(Status::unimplemented("deploy_process"))
+    }
+
+    async fn deploy_resource(
+        &self,
+        _: Request<DeployResourceRequest>,
+    ) -> Result<Response<DeployResourceResponse>, Status> {
+        Err(Status::unimplemented("deploy_resource"))
+    }
+
+    async fn fail_job(
+        &self,
+        _: Request<FailJobRequest>,
+    ) -> Result<Response<FailJobResponse>, Status> {
+        Err(Status::unimplemented("fail_job"))
+    }
+
+    async fn throw_error(
+        &self,
+        _: Request<ThrowErrorRequest>,
+    ) -> Result<Response<ThrowErrorResponse>, Status> {
+        Err(Status::unimplemented("throw_error"))
+    }
+
+    async fn publish_message(
+        &self,
+        _: Request<PublishMessageRequest>,
+    ) -> Result<Response<PublishMessageResponse>, Status> {
+        Err(Status::unimplemented("publish_message"))
+    }
+
+    async fn resolve_incident(
+        &self,
+        _: Request<ResolveIncidentRequest>,
+    ) -> Result<Response<ResolveIncidentResponse>, Status> {
+        Err(Status::unimplemented("resolve_incident"))
+    }
+
+    async fn set_variables(
+        &self,
+        _: Request<SetVariablesRequest>,
+    ) -> Result<Response<SetVariablesResponse>, Status> {
+        Err(Status::unimplemented("set_variables"))
+    }
+
+    async fn topology(
+        &self,
+        _: Request<TopologyRequest>,
+    ) -> Result<Response<TopologyResponse>, Status> {
+        Err(Status::unimplemented("topology"))
+    }
+
+    async fn update_job_retries(
+        &self,
+        _: Request<UpdateJobRetriesRequest>,
+    ) -> Result<Response<UpdateJobRetriesResponse>, Status> {
+        Err(Status::unimplemented("update_job_retries"))
+    }
+
+    async fn modify_process_instance(
+        &self,
+        _: Request<ModifyProcessInstanceRequest>,
+    ) -> Result<Response<ModifyProcessInstanceResponse>, Status> {
+        Err(Status::unimplemented("modify_process_instance"))
+    }
+}
+
+/// Serves a stub gateway with `JOBS` jobs and returns a client for it, along with the keys of the
+/// jobs as they are completed.
+async fn serve(stub: Stub) -> (ZeebeClient, mpsc::Receiver<i64>) {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_test_writer()
+        .try_init();
+
+    let (completed, completions) = mpsc::channel(JOBS as usize);
+    let gateway = StubGateway {
+        stub,
+        pending: Arc::new(Mutex::new((1..=JOBS).collect())),
+        broken_once: AtomicBool::new(false),
+        completed,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(
+        Server::builder()
+            .add_service(gateway_server::GatewayServer::new(gateway))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let client = zeebe_client::connect(
+        Connection {
+            insecure: true,
+            addr: addr.to_string(),
+            ..Default::default()
+        },
+        Authentication::Unauthenticated,
+    )
+    .await
+    .unwrap();
+    (client, completions)
+}
+
+fn worker(client: ZeebeClient) -> WorkerBuilder {
+    WorkerBuilder::new(client, "stub")
+        .handler(|job: Job| async move { job.variables::<serde_json::Value>().into_job_outcome() })
+}
+
+/// Runs `worker` until all jobs are completed, or the stub rejected their completion, and
+/// returns their keys in order.
+async fn complete_all(worker: Worker, mut completions: mpsc::Receiver<i64>) -> Vec<i64> {
+    let shutdown = worker.shutdown_token();
+    let running = tokio::spawn(worker.run());
+    let mut completed = tokio::time::timeout(TIMEOUT, async {
+        let mut completed = Vec::new();
+        while completed.len() < JOBS as usize {
+            completed.push(completions.recv().await.expect("stub gateway is running"));
+        }
+        completed
+    })
+    .await
+    .expect("all jobs are completed in time");
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+    completed.sort();
+    completed
+}
+
+#[tokio::test]
+async fn streams_jobs_and_reconnects() {
+    let (client, completions) = serve(Stub::default()).await;
+    let worker = worker(client).stream_jobs(true).build().unwrap();
+    assert_eq!(
+        complete_all(worker, completions).await,
+        (1..=JOBS).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn falls_back_to_polling() {
+    let (client, completions) = serve(Stub {
+        legacy: true,
+        ..Stub::default()
+    })
+    .await;
+    let worker = worker(client).stream_jobs(true).build().unwrap();
+    assert_eq!(
+        complete_all(worker, completions).await,
+        (1..=JOBS).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn closes_the_stream_while_at_capacity() {
+    let (client, completions) = serve(Stub::default()).await;
+    let worker = worker(client)
+        .stream_jobs(true)
+        .max_jobs_active(1)
+        .handler(|job: Job| async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            job.variables::<serde_json::Value>().into_job_outcome()
+        })
+        .build()
+        .unwrap();
+    assert_eq!(
+        complete_all(worker, completions).await,
+        (1..=JOBS).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn rejects_rate_limits_with_streaming() {
+    let (client, _) = serve(Stub::default()).await;
+    let built = worker(client)
+        .stream_jobs(true)
+        .rate_limit(RateLimit::per_second(10.0))
+        .build();
+    assert!(matches!(built, Err(WorkerError::RateLimitedStream)));
+}
+
+#[tokio::test]
+async fn skips_jobs_past_their_deadline() {
+    let (client, mut completions) = serve(Stub {
+        legacy: true,
+        deadline: 0,
+        ..Stub::default()
+    })
+    .await;
+    let handled = Arc::new(AtomicUsize::new(0));
+    let worker = WorkerBuilder::new(client, "stub")
+        .handler({
+            let handled = handled.clone();
+            move |_: Job| {
+                handled.fetch_add(1, Ordering::SeqCst);
+                async { JobOutcome::complete() }
+            }
+        })
+        .build()
+        .unwrap();
+    let shutdown = worker.shutdown_token();
+    let running = tokio::spawn(worker.run());
+    // the first poll activates all jobs
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    shutdown.cancel();
+    running.await.unwrap().unwrap();
+    assert_eq!(handled.load(Ordering::SeqCst), 0);
+    assert!(completions.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn shares_max_in_flight_across_workers() {
+    let (client, mut completions) = serve(Stub {
+        legacy: true,
+        ..Stub::default()
+    })
+    .await;
+    let registry = WorkerRegistry::new(client).max_in_flight(2).unwrap();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let most_in_flight = Arc::new(AtomicUsize::new(0));
+    // the stub hands out its jobs to either job type
+    for job_type in ["stub", "other"] {
+        let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+        let worker = registry
+            .worker(job_type)
+            .max_jobs_active(JOBS as usize)
+            .handler(move |_: Job| {
+                let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+                async move {
+                    let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_in_flight.fetch_max(running, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    JobOutcome::complete()
+                }
+            })
+            .build()
+            .unwrap();
+        registry.register(worker).unwrap();
+    }
+
+    registry.start_all();
+    tokio::time::timeout(TIMEOUT, async {
+        for _ in 0..JOBS {
+            completions.recv().await.expect("stub gateway is running");
+        }
+    })
+    .await
+    .expect("all jobs are completed in time");
+    registry.stop_all().await;
+    assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+    assert_eq!(registry.in_flight(), Some(0));
+}
+
+#[test]
+fn drops_outcomes_of_stale_jobs() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    // the worker creates its metrics when it starts running, on this thread
+    let completed = metrics::with_local_recorder(&recorder, || {
+        runtime.block_on(async {
+            let (client, completions) = serve(Stub {
+                legacy: true,
+                stale: true,
+                ..Stub::default()
+            })
+            .await;
+            complete_all(worker(client).build().unwrap(), completions).await
+        })
+    });
+    assert_eq!(completed, (1..=JOBS).collect::<Vec<_>>());
+
+    let counters: HashMap<_, _> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter_map(|(key, _, _, value)| match value {
+            DebugValue::Counter(count) => Some((key.key().name().to_owned(), count)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(counters[zeebe_worker::metrics::JOBS_STALE], JOBS as u64);
+    assert_eq!(counters[zeebe_worker::metrics::JOBS_COMPLETED], 0);
+}
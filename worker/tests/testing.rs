@@ -0,0 +1,217 @@
+//! Runs handlers and workers through the testing harness.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zeebe_worker::{
+    job_handler, testing::TestJob, Job, JobCommand, JobOutcome, WorkerBuilder, ZeebeVariables,
+};
+
+#[derive(Deserialize, ZeebeVariables)]
+#[serde(rename_all = "camelCase")]
+struct Payment {
+    order_id: String,
+    amount: f64,
+}
+
+#[derive(Serialize)]
+struct Receipt {
+    order_id: String,
+    charged: f64,
+}
+
+#[job_handler(type = "payment", name = "payments")]
+async fn charge(payment: Payment) -> Result<Receipt, String> {
+    if payment.amount <= 0.0 {
+        return Err(format!("invalid amount {}", payment.amount));
+    }
+    Ok(Receipt {
+        order_id: payment.order_id,
+        charged: payment.amount,
+    })
+}
+
+/// Completes with the variables the handler received.
+async fn echo(job: Job) -> JobOutcome {
+    JobOutcome::complete_with(&job.variables::<Value>().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn completes_with_the_handler_result() {
+    let command = TestJob::new("echo")
+        .key(7)
+        .variables(&json!({ "a": 1 }))
+        .run(&echo)
+        .await;
+    assert_eq!(command.kind(), "complete");
+    assert_eq!(command.job_key(), 7);
+    match command {
+        JobCommand::Complete(request) => assert_eq!(request.variables, r#"{"a":1}"#),
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn fails_with_one_retry_less() {
+    let handler = |_: Job| async { JobOutcome::fail("downstream unavailable") };
+    match TestJob::new("echo").retries(3).run(&handler).await {
+        JobCommand::Fail(request) => {
+            assert_eq!(request.retries, 2);
+            assert_eq!(request.error_message, "downstream unavailable");
+        }
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn fails_with_explicit_retries_and_back_off() {
+    let handler = |_: Job| async {
+        JobOutcome::Fail {
+            error_message: "rate limited".to_owned(),
+            retries: Some(5),
+            retry_back_off: Duration::from_secs(30),
+            variables: String::new(),
+        }
+    };
+    match TestJob::new("echo").retries(1).run(&handler).await {
+        JobCommand::Fail(request) => {
+            assert_eq!(request.retries, 5);
+            assert_eq!(request.retry_back_off, 30_000);
+        }
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn fails_overloaded_jobs_with_one_retry_less() {
+    let handler = |_: Job| async { JobOutcome::overloaded("database saturated") };
+    match TestJob::new("echo").retries(3).run(&handler).await {
+        JobCommand::Fail(request) => {
+            assert_eq!(request.retries, 2);
+            assert_eq!(request.error_message, "database saturated");
+        }
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn never_fails_with_negative_retries() {
+    let handler = |_: Job| async { JobOutcome::fail("still broken") };
+    match TestJob::new("echo").retries(0).run(&handler).await {
+        JobCommand::Fail(request) => assert_eq!(request.retries, 0),
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn throws_errors() {
+    let handler = |_: Job| async { JobOutcome::throw_error("OUT_OF_STOCK", "no items left") };
+    match TestJob::new("echo").key(3).run(&handler).await {
+        JobCommand::ThrowError(request) => {
+            assert_eq!(request.job_key, 3);
+            assert_eq!(request.error_code, "OUT_OF_STOCK");
+            assert_eq!(request.error_message, "no items left");
+        }
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn hands_cancelled_jobs_to_handlers() {
+    let handler = |job: Job| async move {
+        match job.is_cancelled() {
+            true => JobOutcome::fail("cancelled"),
+            false => JobOutcome::complete(),
+        }
+    };
+    assert_eq!(TestJob::new("echo").run(&handler).await.kind(), "complete");
+    assert_eq!(
+        TestJob::new("echo").cancelled().run(&handler).await.kind(),
+        "fail"
+    );
+}
+
+#[tokio::test]
+async fn passes_only_fetched_variables_to_workers() {
+    let worker = WorkerBuilder::new(zeebe_worker::testing::client(), "echo")
+        .fetch_variables(vec!["a".to_owned(), "missing".to_owned()])
+        .handler(echo)
+        .build()
+        .unwrap();
+    let command = TestJob::new("echo")
+        .variables(&json!({ "a": 1, "b": 2 }))
+        .run_worker(&worker)
+        .await;
+    match command {
+        JobCommand::Complete(request) => assert_eq!(request.variables, r#"{"a":1}"#),
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn passes_all_variables_without_fetch_variables() {
+    let worker = WorkerBuilder::new(zeebe_worker::testing::client(), "echo")
+        .handler(echo)
+        .build()
+        .unwrap();
+    let command = TestJob::new("echo")
+        .variables(&json!({ "a": 1, "b": 2 }))
+        .run_worker(&worker)
+        .await;
+    match command {
+        JobCommand::Complete(request) => assert_eq!(request.variables, r#"{"a":1,"b":2}"#),
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn runs_job_handler_workers() {
+    let worker = charge_worker(zeebe_worker::testing::client())
+        .build()
+        .unwrap();
+    let command = TestJob::new("payment")
+        .variables(&json!({ "orderId": "o-1", "amount": 42.0, "customer": "c-1" }))
+        .run_worker(&worker)
+        .await;
+    match command {
+        JobCommand::Complete(request) => {
+            let variables: Value = serde_json::from_str(&request.variables).unwrap();
+            assert_eq!(variables, json!({ "order_id": "o-1", "charged": 42.0 }));
+        }
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn fails_job_handler_workers_on_errors() {
+    let worker = charge_worker(zeebe_worker::testing::client())
+        .build()
+        .unwrap();
+    let command = TestJob::new("payment")
+        .variables(&json!({ "orderId": "o-1", "amount": 0.0 }))
+        .run_worker(&worker)
+        .await;
+    match command {
+        JobCommand::Fail(request) => assert_eq!(request.error_message, "invalid amount 0"),
+        other => panic!("unexpected command {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn reports_all_missing_variables() {
+    let worker = charge_worker(zeebe_worker::testing::client())
+        .build()
+        .unwrap();
+    let command = TestJob::new("payment")
+        .variables(&json!({ "customer": "c-1" }))
+        .run_worker(&worker)
+        .await;
+    match command {
+        JobCommand::Fail(request) => assert_eq!(
+            request.error_message,
+            "failed to deserialize job variables: missing variables: orderId, amount"
+        ),
+        other => panic!("unexpected command {:?}", other),
+    }
+}